@@ -0,0 +1,43 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use uwu_rs::Uwu;
+
+const SAMPLE: &str = "The quick brown fox jumps over the lazy dog. What a cute little fox! \
+    I love small fluffy animals, but this one is really stupid. Meow, said the cat. ";
+
+fn bench_uwuify(c: &mut Criterion) {
+    let configs = [
+        ("default", Uwu::new()),
+        ("light", Uwu::builder().lowercase().w_replace().build()),
+    ];
+
+    for (name, uwu) in configs {
+        let mut group = c.benchmark_group(name);
+        for repeat in [1, 10_000] {
+            let input = SAMPLE.repeat(repeat);
+            group.throughput(Throughput::Bytes(input.len() as u64));
+
+            let engine = uwu.compile().unwrap();
+            group.bench_with_input(
+                BenchmarkId::new("engine", input.len()),
+                &input,
+                |b, input| b.iter(|| engine.uwuify(input).unwrap()),
+            );
+
+            let mut output = Vec::with_capacity(input.len() * 2);
+            group.bench_with_input(
+                BenchmarkId::new("stream", input.len()),
+                &input,
+                |b, input| {
+                    b.iter(|| {
+                        output.clear();
+                        engine.uwuify_stream(input.as_bytes(), &mut output).unwrap()
+                    })
+                },
+            );
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_uwuify);
+criterion_main!(benches);
@@ -10,7 +10,7 @@ use tracing_subscriber::fmt;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 
-use uwu_rs::{Uwu, UwuError};
+use uwu_rs::{HtmlOptions, Uwu, UwuBuilder, UwuError};
 
 /// Converts text to an uwuified version.
 #[derive(Parser, Debug)]
@@ -28,6 +28,26 @@ struct Cli {
     #[arg(short, long, value_name = "FILE")]
     output: Option<PathBuf>,
 
+    /// Treat the input as Markdown, only uwuifying its prose.
+    #[arg(short, long, conflicts_with = "html")]
+    markdown: bool,
+
+    /// Treat the input as HTML, only uwuifying its visible text.
+    #[arg(long)]
+    html: bool,
+
+    /// With --html, also uwuify the values of the attribute, e.g. 'alt' or 'title'.
+    #[arg(long = "html-attribute", value_name = "NAME", requires = "html")]
+    html_attributes: Vec<String>,
+
+    /// The style to uwuify with, e.g. 'light' or 'catgirl'. See --list-styles.
+    #[arg(short, long, value_name = "NAME")]
+    style: Option<String>,
+
+    /// List the available styles and exit.
+    #[arg(long)]
+    list_styles: bool,
+
     /// Output in JSON format.
     #[arg(long)]
     json: bool,
@@ -79,8 +99,29 @@ fn entrypoint() -> Result<(), UwuCliError> {
 
     trace!("Arguments: {args:?}");
 
+    if args.list_styles {
+        for style in UwuBuilder::styles().iter() {
+            println!("{:<10} {}", style.name(), style.description());
+        }
+        return Ok(());
+    }
+
     let input = read_input(&args)?;
-    let uwuified = Uwu::new().uwuify(input)?;
+    let uwu = match &args.style {
+        Some(style) => UwuBuilder::from_style(style)?.build(),
+        None => Uwu::new(),
+    };
+    let uwuified = if args.markdown {
+        uwu.uwuify_markdown(input)?
+    } else if args.html {
+        let mut options = HtmlOptions::new();
+        for attribute in &args.html_attributes {
+            options.attribute(attribute);
+        }
+        uwu.uwuify_html(input, &options)?
+    } else {
+        uwu.uwuify(input)?
+    };
     write_output(uwuified, &args)?;
 
     Ok(())
@@ -1,4 +1,4 @@
-use uwu_rs::{Uwu, UwuBuilder};
+use uwu_rs::{Chance, Uwu, UwuBuilder, UwuError};
 use wasm_bindgen::prelude::*;
 
 extern crate wee_alloc;
@@ -10,27 +10,33 @@ static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 #[wasm_bindgen]
 #[derive(Clone)]
 pub struct UwuOptions {
-    /// Enables the lowercase feature, e.g. 'Hello' becomes 'hello'. Beware that the other features
-    /// may misbehave if this feature is disabled.
+    /// Enables the lowercase feature, e.g. 'Hello' becomes 'hello'. If this feature is disabled,
+    /// the other features preserve the case of the input, e.g. 'Hello' becomes 'Hewwo'.
     pub lowercase: bool,
     /// Enables expression replacement, e.g. 'what' becomes 'nani'.
     pub expressions: bool,
+    /// Enables adding a 'y' between an 'n' and a vowel, e.g. 'nice' becomes 'nyice'.
+    pub nya: bool,
     /// Enables replacement of 'l' and 'r' with 'w', e.g. 'lovely' becomes 'wovewy'.
     pub w_replace: bool,
     /// Enables stutter, e.g. 'hello' becomes 'h-hello'.
     pub stutter: bool,
     /// Specifies how frequent the stutter is. A value of 1 will add stutter to every word, whereas
-    /// a value of 2 will add stutter every 2 words on average.
-    pub stutter_chance: u8,
+    /// a value of 2 will add stutter every 2 words on average. Values below 1 are a probability,
+    /// e.g. 0.3 adds stutter to 30% of words.
+    pub stutter_chance: f64,
     /// Enables adding emojis after punctuation, e.g. 'goodbye.' becomes 'goodbye. OwO'.
     pub emojis: bool,
     /// Specifies how frequently emojis are added. A value of 1 will add emojis after every
     /// punctuation, whereas a value of 2 will add emojis every 2 punctuation marks on average.
-    pub emojis_chance: u8,
+    /// Values below 1 are a probability, e.g. 0.3 adds emojis after 30% of punctuation marks.
+    pub emojis_chance: f64,
 }
 
-impl From<UwuOptions> for UwuBuilder {
-    fn from(options: UwuOptions) -> Self {
+impl TryFrom<UwuOptions> for UwuBuilder {
+    type Error = UwuError;
+
+    fn try_from(options: UwuOptions) -> Result<Self, Self::Error> {
         let mut builder = Self::new();
 
         if options.lowercase {
@@ -39,36 +45,74 @@ impl From<UwuOptions> for UwuBuilder {
         if options.expressions {
             builder.expressions();
         }
+        if options.nya {
+            builder.nya();
+        }
         if options.w_replace {
             builder.w_replace();
         }
         if options.stutter {
-            builder.stutter(options.stutter_chance);
+            builder.stutter_chance(parse_chance("stutter_chance", options.stutter_chance)?);
         }
         if options.emojis {
-            builder.emojis(options.emojis_chance);
+            builder.emojis_chance(parse_chance("emojis_chance", options.emojis_chance)?);
         }
 
-        builder
+        Ok(builder)
     }
 }
 
-impl From<UwuOptions> for Uwu {
-    fn from(options: UwuOptions) -> Self {
-        UwuBuilder::from(options).build()
+impl TryFrom<UwuOptions> for Uwu {
+    type Error = UwuError;
+
+    fn try_from(options: UwuOptions) -> Result<Self, Self::Error> {
+        UwuBuilder::try_from(options)?.try_build()
+    }
+}
+
+/// Converts the input text into an uwuified version. Throws an error if the options are invalid,
+/// e.g. a chance of 0.
+#[wasm_bindgen]
+pub fn uwuify(input: &str, options: Option<UwuOptions>) -> Result<String, JsError> {
+    let uwu = parse_options(options).map_err(|err| JsError::new(&err.to_string()))?;
+    Ok(uwu.uwuify(input).unwrap_or(input.to_string()))
+}
+
+/// Converts the input text into an uwuified version, with a built-in style, e.g. 'light' or
+/// 'catgirl'. Unknown styles return the input as is.
+#[wasm_bindgen]
+pub fn uwuify_style(input: &str, style: &str) -> String {
+    match UwuBuilder::from_style(style) {
+        Ok(builder) => builder.build().uwuify(input).unwrap_or(input.to_string()),
+        Err(_) => input.to_string(),
     }
 }
 
+/// The names of the built-in styles.
 #[wasm_bindgen]
-pub fn uwuify(input: &str, options: Option<UwuOptions>) -> String {
-    let uwu = parse_options(options);
-    uwu.uwuify(input).unwrap_or(input.to_string())
+pub fn styles() -> Vec<String> {
+    UwuBuilder::styles().names().map(str::to_string).collect()
 }
 
-fn parse_options(options: Option<UwuOptions>) -> Uwu {
+fn parse_options(options: Option<UwuOptions>) -> Result<Uwu, UwuError> {
     if let Some(options) = options {
-        Uwu::from(options)
+        Uwu::try_from(options)
     } else {
-        Uwu::new()
+        Ok(Uwu::new())
+    }
+}
+
+/// Parses a chance of 1 in N times, or a probability below 1. Numbers from JavaScript can be
+/// anything, so they are checked instead of being truncated to a `u8`.
+fn parse_chance(field: &str, value: f64) -> Result<Chance, UwuError> {
+    if (0.0..1.0).contains(&value) {
+        return Chance::probability(value);
+    }
+    if value.fract() != 0.0 || !(1.0..=u8::MAX as f64).contains(&value) {
+        return Err(UwuError::InvalidConfig(format!(
+            "{field} must be a whole number up to {} or a probability below 1, got {value}",
+            u8::MAX
+        )));
     }
+    Ok(Chance::one_in(value as u8))
 }
@@ -1,4 +1,12 @@
-use crate::Uwu;
+use alloc::string::String;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use crate::{
+    dict, Chance, Dictionary, EmojiSet, Expression, Locale, MatchKind, Protection, PunctuationSet,
+    Seed, StutterMode, StutterOptions, StyleRegistry, Uwu, UwuEngine, UwuError,
+};
 
 /// A builder for the `Uwu` instance.
 ///
@@ -12,25 +20,85 @@ use crate::Uwu;
 ///     .emojis(1)
 ///     .build();
 /// ```
-#[derive(Copy, Clone, Default, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(
+        into = "crate::config::UwuConfig",
+        try_from = "crate::config::UwuConfig"
+    )
+)]
 pub struct UwuBuilder {
-    /// Enables the lowercase feature, e.g. 'Hello' becomes 'hello'. Beware that the other features
-    /// may misbehave if this feature is disabled.
+    /// Enables the lowercase feature, e.g. 'Hello' becomes 'hello'. The input is case folded, so
+    /// e.g. 'Straße' becomes 'strasse'. If this feature is disabled, the other features preserve
+    /// the case of the input, e.g. 'Hello' becomes 'Hewwo'.
     pub lowercase: bool,
+    /// Specifies the locale rules used by the lowercase feature, e.g. `Locale::Turkic` converts 'I'
+    /// to 'ı'.
+    pub locale: Locale,
     /// Enables expression replacement, e.g. 'what' becomes 'nani'.
     pub expressions: bool,
+    /// Specifies the expressions replaced by the expressions feature.
+    pub dictionary: Dictionary,
+    /// Enables the nya feature, which adds a 'y' between an 'n' and a vowel, e.g. 'nice anime'
+    /// becomes 'nyice anyime'.
+    pub nya: bool,
+    /// Specifies the words left alone by the nya feature, ignoring case, e.g. 'canon'. Defaults to
+    /// words whose 'n' is followed by a silent 'e', e.g. 'one' and 'done', like [`Uwu::default`].
+    pub nya_exceptions: Vec<String>,
     /// Enables replacement of 'l' and 'r' with 'w', e.g. 'lovely' becomes 'wovewy'.
     pub w_replace: bool,
-    /// Enables stutter, e.g. 'hello' becomes 'h-hello'.
+    /// Enables stutter, e.g. 'hello' becomes 'h-hello', for words after whitespace or an opening
+    /// bracket or quote.
     pub stutter: bool,
-    /// Specifies how frequent the stutter is. A value of 1 will add stutter to every word, whereas
-    /// a value of 2 will add stutter every 2 words on average.
-    pub stutter_chance: u8,
+    /// Specifies how frequent the stutter is. A chance of 1 in 1 will add stutter to every word,
+    /// whereas a chance of 1 in 2 or 50% will add stutter every 2 words on average.
+    pub stutter_chance: Chance,
+    /// Specifies which words stutter and how, e.g. skipping short words or repeating the stutter.
+    pub stutter_options: StutterOptions,
     /// Enables adding emojis after punctuation, e.g. 'goodbye.' becomes 'goodbye. OwO'.
     pub emojis: bool,
-    /// Specifies how frequently emojis are added. A value of 1 will add emojis after every
-    /// punctuation, whereas a value of 2 will add emojis every 2 punctuation marks on average.
-    pub emojis_chance: u8,
+    /// Specifies how frequently emojis are added. A chance of 1 in 1 will add emojis after every
+    /// punctuation, whereas a chance of 1 in 2 or 50% will add emojis every 2 punctuation marks on
+    /// average.
+    pub emojis_chance: Chance,
+    /// Specifies the emojis added by the emojis feature.
+    pub emoji_set: EmojiSet,
+    /// Specifies the punctuation marks after which the emojis feature adds emojis.
+    pub punctuation_set: PunctuationSet,
+    /// Specifies the kinds of tokens passed through verbatim, e.g. URLs or @mentions.
+    pub protection: Protection,
+    /// Specifies the words passed through verbatim, e.g. brand names like 'Rust'. They only match
+    /// whole words, with the same case.
+    pub protected_words: Vec<String>,
+    /// Specifies how the random number generator used by the stutter and emojis features is
+    /// seeded. Defaults to a fixed seed, so the same input always produces the same output.
+    pub seed: Seed,
+}
+
+impl Default for UwuBuilder {
+    fn default() -> Self {
+        Self {
+            lowercase: false,
+            locale: Locale::Root,
+            expressions: false,
+            dictionary: Dictionary::default(),
+            nya: false,
+            nya_exceptions: dict::NYA_EXCEPTIONS.map(String::from).to_vec(),
+            w_replace: false,
+            stutter: false,
+            stutter_chance: Chance::NEVER,
+            stutter_options: StutterOptions::default(),
+            emojis: false,
+            emojis_chance: Chance::NEVER,
+            emoji_set: EmojiSet::default(),
+            punctuation_set: PunctuationSet::default(),
+            protection: Protection::NONE,
+            protected_words: Vec::new(),
+            seed: Seed::default(),
+        }
+    }
 }
 
 impl UwuBuilder {
@@ -39,54 +107,388 @@ impl UwuBuilder {
         Self::default()
     }
 
-    /// Enables the lowercase feature, e.g. 'Hello' becomes 'hello'.
+    /// Create a new builder with the configuration of a built-in style, e.g. `light` or `catgirl`.
+    ///
+    /// See [`StyleRegistry`] for the list of built-in styles.
+    pub fn from_style(name: &str) -> Result<Self, UwuError> {
+        StyleRegistry::new().builder(name)
+    }
+
+    /// The registry of built-in styles, which can be extended with custom ones.
+    pub fn styles() -> StyleRegistry {
+        StyleRegistry::new()
+    }
+
+    /// Enables the lowercase feature, e.g. 'Hello' becomes 'hello' and 'Straße' becomes 'strasse'.
     pub fn lowercase(&mut self) -> &mut Self {
         self.lowercase = true;
         self
     }
 
+    /// Sets the locale rules used by the lowercase feature, e.g. `Locale::Turkic` converts 'I' to
+    /// 'ı'.
+    pub fn locale(&mut self, locale: Locale) -> &mut Self {
+        self.locale = locale;
+        self
+    }
+
     /// Enables expression replacement, e.g. 'what' becomes 'nani'.
     pub fn expressions(&mut self) -> &mut Self {
         self.expressions = true;
         self
     }
 
+    /// Adds an expression that only matches whole words to the dictionary, or replaces the
+    /// replacement of an existing pattern, e.g. 'hello' becomes 'henwo'.
+    pub fn add_expression<P: AsRef<str>, R: Into<String>>(
+        &mut self,
+        pattern: P,
+        replacement: R,
+    ) -> &mut Self {
+        self.dictionary.insert(pattern, replacement);
+        self
+    }
+
+    /// Adds an expression to the dictionary, or replaces an existing one with the same pattern. The
+    /// expression can also match parts of words or inflected forms, e.g. 'loved' becomes 'luved'.
+    pub fn insert_expression(&mut self, expression: Expression) -> &mut Self {
+        self.dictionary.insert_expression(expression);
+        self
+    }
+
+    /// Removes an expression from the dictionary.
+    pub fn remove_expression<P: AsRef<str>>(&mut self, pattern: P) -> &mut Self {
+        self.dictionary.remove(pattern);
+        self
+    }
+
+    /// Removes every expression from the dictionary, including the default ones.
+    pub fn clear_expressions(&mut self) -> &mut Self {
+        self.dictionary.clear();
+        self
+    }
+
+    /// Sets the semantics used when multiple expressions match overlapping text.
+    pub fn expressions_match_kind(&mut self, match_kind: MatchKind) -> &mut Self {
+        self.dictionary.set_match_kind(match_kind);
+        self
+    }
+
+    /// Replaces the dictionary of expressions.
+    pub fn dictionary(&mut self, dictionary: Dictionary) -> &mut Self {
+        self.dictionary = dictionary;
+        self
+    }
+
+    /// Loads the expressions of a TOML or JSON file into the dictionary, replacing the existing
+    /// ones with the same pattern. The match semantics of the file replace the current ones.
+    ///
+    /// See [`Dictionary::load`].
+    #[cfg(feature = "std")]
+    pub fn load_dictionary<P: AsRef<Path>>(&mut self, path: P) -> Result<&mut Self, UwuError> {
+        let dictionary = Dictionary::load(path)?;
+        self.dictionary.set_match_kind(dictionary.match_kind());
+        self.dictionary.extend(dictionary);
+        Ok(self)
+    }
+
+    /// Enables the nya feature, e.g. 'nice anime' becomes 'nyice anyime'.
+    pub fn nya(&mut self) -> &mut Self {
+        self.nya = true;
+        self
+    }
+
+    /// Adds a word left alone by the nya feature, ignoring case, e.g. 'canon'.
+    pub fn add_nya_exception<T: Into<String>>(&mut self, word: T) -> &mut Self {
+        self.nya_exceptions.push(word.into());
+        self
+    }
+
+    /// Removes every word left alone by the nya feature.
+    pub fn clear_nya_exceptions(&mut self) -> &mut Self {
+        self.nya_exceptions.clear();
+        self
+    }
+
     /// Enables replacement of 'l' and 'r' with 'w', e.g. 'lovely' becomes 'wovewy'.
     pub fn w_replace(&mut self) -> &mut Self {
         self.w_replace = true;
         self
     }
 
-    /// Enables stutter, e.g. 'hello' becomes 'h-hello'.
+    /// Enables stutter, e.g. 'hello' becomes 'h-hello', for 1 in `chance` words.
     pub fn stutter(&mut self, chance: u8) -> &mut Self {
+        self.stutter_chance(Chance::one_in(chance))
+    }
+
+    /// Enables stutter, e.g. 'hello' becomes 'h-hello', with a chance given as 1 in N words or as a
+    /// probability.
+    pub fn stutter_chance(&mut self, chance: Chance) -> &mut Self {
         self.stutter = true;
         self.stutter_chance = chance;
         self
     }
 
-    /// Enables adding emojis after punctuation, e.g. 'goodbye.' becomes 'goodbye. OwO'.
+    /// Enables stutter on every `n`th word that may stutter, e.g. 3 stutters the 3rd, 6th and 9th
+    /// words, instead of picking them at random.
+    pub fn stutter_every(&mut self, n: u32) -> &mut Self {
+        self.stutter = true;
+        self.stutter_options.mode = StutterMode::EveryNth(n);
+        self
+    }
+
+    /// Sets the minimum number of letters of a word that stutters, e.g. 2 skips 'a' and 'I'.
+    pub fn stutter_min_length(&mut self, min_length: usize) -> &mut Self {
+        self.stutter_options.min_length = min_length;
+        self
+    }
+
+    /// Adds a word that never stutters, ignoring case, e.g. 'the'.
+    pub fn add_stutter_stopword<T: Into<String>>(&mut self, word: T) -> &mut Self {
+        self.stutter_options.stopwords.push(word.into());
+        self
+    }
+
+    /// Lets a word stutter up to `max_repeats` times, e.g. 'h-h-hello', stuttering once more with
+    /// the given chance each time.
+    pub fn stutter_repeats(&mut self, max_repeats: u8, chance: Chance) -> &mut Self {
+        self.stutter_options.max_repeats = max_repeats;
+        self.stutter_options.repeat_chance = chance;
+        self
+    }
+
+    /// Repeats the consonants a word starts with, e.g. 'stop' becomes 'st-stop', instead of its
+    /// first letter.
+    pub fn stutter_consonant_clusters(&mut self) -> &mut Self {
+        self.stutter_options.consonant_clusters = true;
+        self
+    }
+
+    /// Enables adding emojis after punctuation, e.g. 'goodbye.' becomes 'goodbye. OwO', after 1 in
+    /// `chance` punctuation marks.
     pub fn emojis(&mut self, chance: u8) -> &mut Self {
+        self.emojis_chance(Chance::one_in(chance))
+    }
+
+    /// Enables adding emojis after punctuation, e.g. 'goodbye.' becomes 'goodbye. OwO', with a
+    /// chance given as 1 in N punctuation marks or as a probability.
+    pub fn emojis_chance(&mut self, chance: Chance) -> &mut Self {
         self.emojis = true;
         self.emojis_chance = chance;
         self
     }
 
+    /// Adds an emoji to the set, or replaces the weight of an existing one. An emoji with a weight
+    /// of 2 is picked twice as often as an emoji with a weight of 1.
+    pub fn add_emoji<T: Into<String>>(&mut self, text: T, weight: u32) -> &mut Self {
+        self.emoji_set.insert(text, weight);
+        self
+    }
+
+    /// Removes an emoji from the set.
+    pub fn remove_emoji<T: AsRef<str>>(&mut self, text: T) -> &mut Self {
+        self.emoji_set.remove(text);
+        self
+    }
+
+    /// Removes every emoji from the set, including the default ones.
+    pub fn clear_emojis(&mut self) -> &mut Self {
+        self.emoji_set.clear();
+        self
+    }
+
+    /// Replaces the set of emojis.
+    pub fn emoji_set(&mut self, emoji_set: EmojiSet) -> &mut Self {
+        self.emoji_set = emoji_set;
+        self
+    }
+
+    /// Loads the emojis of a TOML or JSON file into the set, replacing the weights of the existing
+    /// ones.
+    ///
+    /// See [`EmojiSet::load`].
+    #[cfg(feature = "std")]
+    pub fn load_emoji_set<P: AsRef<Path>>(&mut self, path: P) -> Result<&mut Self, UwuError> {
+        self.emoji_set.extend(EmojiSet::load(path)?);
+        Ok(self)
+    }
+
+    /// Adds a punctuation mark after which emojis are added when it is followed by whitespace or
+    /// the end of the input, e.g. ';'.
+    pub fn add_punctuation<T: Into<String>>(&mut self, text: T) -> &mut Self {
+        self.punctuation_set.insert(text);
+        self
+    }
+
+    /// Adds a punctuation mark after which emojis are added even when it is not followed by
+    /// whitespace, e.g. '。'.
+    pub fn add_standalone_punctuation<T: Into<String>>(&mut self, text: T) -> &mut Self {
+        self.punctuation_set.insert_standalone(text);
+        self
+    }
+
+    /// Removes a punctuation mark from the set.
+    pub fn remove_punctuation<T: AsRef<str>>(&mut self, text: T) -> &mut Self {
+        self.punctuation_set.remove(text);
+        self
+    }
+
+    /// Removes every punctuation mark from the set, including the default ones.
+    pub fn clear_punctuation(&mut self) -> &mut Self {
+        self.punctuation_set.clear();
+        self
+    }
+
+    /// Replaces the set of punctuation marks.
+    pub fn punctuation_set(&mut self, punctuation_set: PunctuationSet) -> &mut Self {
+        self.punctuation_set = punctuation_set;
+        self
+    }
+
+    /// Passes URLs through verbatim, e.g. `https://example.org/rust`.
+    pub fn protect_urls(&mut self) -> &mut Self {
+        self.protection.urls = true;
+        self
+    }
+
+    /// Passes email addresses through verbatim, e.g. 'carol@example.org'.
+    pub fn protect_emails(&mut self) -> &mut Self {
+        self.protection.emails = true;
+        self
+    }
+
+    /// Passes mentions through verbatim, e.g. '@carol'.
+    pub fn protect_mentions(&mut self) -> &mut Self {
+        self.protection.mentions = true;
+        self
+    }
+
+    /// Passes hashtags through verbatim, e.g. '#rustlang'.
+    pub fn protect_hashtags(&mut self) -> &mut Self {
+        self.protection.hashtags = true;
+        self
+    }
+
+    /// Passes emoji shortcodes through verbatim, e.g. ':thumbs_up:'.
+    pub fn protect_shortcodes(&mut self) -> &mut Self {
+        self.protection.shortcodes = true;
+        self
+    }
+
+    /// Passes channel references through verbatim, e.g. '<#C024BE7LR|general>'.
+    pub fn protect_channels(&mut self) -> &mut Self {
+        self.protection.channels = true;
+        self
+    }
+
+    /// Replaces the kinds of tokens passed through verbatim, e.g. `Protection::ALL`.
+    pub fn protection(&mut self, protection: Protection) -> &mut Self {
+        self.protection = protection;
+        self
+    }
+
+    /// Adds a word that is passed through verbatim, e.g. 'Rust' is never lowercased, w-replaced,
+    /// stuttered or replaced by an expression. It only matches whole words, with the same case.
+    pub fn protect_word<T: Into<String>>(&mut self, word: T) -> &mut Self {
+        let word = word.into();
+        if !self.protected_words.contains(&word) {
+            self.protected_words.push(word);
+        }
+        self
+    }
+
+    /// Removes a word from the protected words.
+    pub fn remove_protected_word<T: AsRef<str>>(&mut self, word: T) -> &mut Self {
+        self.protected_words
+            .retain(|protected| protected != word.as_ref());
+        self
+    }
+
+    /// Removes every protected word.
+    pub fn clear_protected_words(&mut self) -> &mut Self {
+        self.protected_words.clear();
+        self
+    }
+
+    /// Sets how the random number generator used by the stutter and emojis features is seeded.
+    pub fn seed(&mut self, seed: Seed) -> &mut Self {
+        self.seed = seed;
+        self
+    }
+
     /// Builds a new `Uwu` instance from the arguments set in this builder.
     pub fn build(&self) -> Uwu {
         Uwu::from(self)
     }
+
+    /// Builds a new `Uwu` instance from the arguments set in this builder, validating them first,
+    /// e.g. that the chance of an enabled feature is not 0.
+    ///
+    /// Example:
+    /// ```
+    /// let error = uwu_rs::UwuBuilder::new().stutter(0).try_build().unwrap_err();
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     "invalid configuration: stutter_chance must be at least 1 in 1 when stutter is enabled, got 1 in 0"
+    /// );
+    /// ```
+    pub fn try_build(&self) -> Result<Uwu, UwuError> {
+        let uwu = self.build();
+        uwu.validate()?;
+        Ok(uwu)
+    }
+
+    /// Builds a new [`UwuEngine`] from the arguments set in this builder, compiling the string
+    /// matchers once so they can be reused on every call.
+    pub fn build_engine(&self) -> Result<UwuEngine, UwuError> {
+        UwuEngine::new(self.build())
+    }
 }
 
 impl From<&UwuBuilder> for Uwu {
     fn from(builder: &UwuBuilder) -> Self {
         Self {
             lowercase: builder.lowercase,
+            locale: builder.locale,
             expressions: builder.expressions,
+            dictionary: builder.dictionary.clone(),
+            nya: builder.nya,
+            nya_exceptions: builder.nya_exceptions.clone(),
             w_replace: builder.w_replace,
             stutter: builder.stutter,
             stutter_chance: builder.stutter_chance,
+            stutter_options: builder.stutter_options.clone(),
             emojis: builder.emojis,
             emojis_chance: builder.emojis_chance,
+            emoji_set: builder.emoji_set.clone(),
+            punctuation_set: builder.punctuation_set.clone(),
+            protection: builder.protection,
+            protected_words: builder.protected_words.clone(),
+            seed: builder.seed,
+        }
+    }
+}
+
+impl From<&Uwu> for UwuBuilder {
+    fn from(uwu: &Uwu) -> Self {
+        Self {
+            lowercase: uwu.lowercase,
+            locale: uwu.locale,
+            expressions: uwu.expressions,
+            dictionary: uwu.dictionary.clone(),
+            nya: uwu.nya,
+            nya_exceptions: uwu.nya_exceptions.clone(),
+            w_replace: uwu.w_replace,
+            stutter: uwu.stutter,
+            stutter_chance: uwu.stutter_chance,
+            stutter_options: uwu.stutter_options.clone(),
+            emojis: uwu.emojis,
+            emojis_chance: uwu.emojis_chance,
+            emoji_set: uwu.emoji_set.clone(),
+            punctuation_set: uwu.punctuation_set.clone(),
+            protection: uwu.protection,
+            protected_words: uwu.protected_words.clone(),
+            seed: uwu.seed,
         }
     }
 }
@@ -113,14 +515,52 @@ mod tests {
 
         let manual_ver = Uwu {
             lowercase: true,
+            locale: Locale::Root,
             expressions: true,
+            dictionary: Dictionary::default(),
+            nya: false,
+            nya_exceptions: dict::NYA_EXCEPTIONS.map(String::from).to_vec(),
             w_replace: true,
             stutter: true,
-            stutter_chance: 4,
+            stutter_chance: Chance::one_in(4),
+            stutter_options: StutterOptions::default(),
             emojis: true,
-            emojis_chance: 1,
+            emojis_chance: Chance::one_in(1),
+            emoji_set: EmojiSet::default(),
+            punctuation_set: PunctuationSet::default(),
+            protection: Protection::NONE,
+            protected_words: Vec::new(),
+            seed: Seed::default(),
         };
 
         assert_eq!(builder_ver, manual_ver);
     }
+
+    #[test]
+    fn assert_try_build() {
+        assert!(Uwu::builder().stutter(2).emojis(1).try_build().is_ok());
+        // Disabled features are not validated
+        assert!(UwuBuilder::new().try_build().is_ok());
+
+        let error = |builder: &UwuBuilder| match builder.try_build() {
+            Err(UwuError::InvalidConfig(message)) => message,
+            result => panic!("unexpected result: {result:?}"),
+        };
+        assert_eq!(
+            error(Uwu::builder().stutter(0)),
+            "stutter_chance must be at least 1 in 1 when stutter is enabled, got 1 in 0"
+        );
+        assert_eq!(
+            error(Uwu::builder().emojis_chance(Chance::NEVER)),
+            "emojis_chance must be greater than 0 when emojis are enabled"
+        );
+
+        // Invalid chances make the engine fail instead of panicking
+        let mut uwu = Uwu::new();
+        uwu.stutter_chance = Chance::one_in(0);
+        assert!(matches!(
+            uwu.uwuify("hello"),
+            Err(UwuError::InvalidConfig(..))
+        ));
+    }
 }
@@ -0,0 +1,128 @@
+use alloc::string::String;
+
+/// Locale-specific rules used when case folding the input.
+///
+/// The default rules follow the Unicode case folding, which works for most scripts. Some languages
+/// require special handling of a few letters, e.g. in Turkish 'I' becomes 'ı' (dotless i) rather
+/// than 'i'.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum Locale {
+    /// The language-independent Unicode case folding.
+    #[default]
+    Root,
+    /// Rules for Turkic languages (Turkish and Azerbaijani), where 'I' folds to 'ı' and 'İ' folds
+    /// to 'i'.
+    Turkic,
+}
+
+impl Locale {
+    /// Applies the full Unicode case folding to the input following the rules of this locale.
+    /// Folding lowercases most letters, e.g. 'ÉCOLE' becomes 'école', but also maps the letters
+    /// whose lowercase forms differ only in spelling, e.g. 'ß' becomes 'ss' and 'ς' becomes 'σ'.
+    ///
+    /// Example:
+    /// ```
+    /// use uwu_rs::Locale;
+    ///
+    /// assert_eq!(Locale::Root.fold_case("Straße"), "strasse");
+    /// assert_eq!(Locale::Root.fold_case("ΟΔΥΣΣΕΥΣ"), "οδυσσευσ");
+    /// assert_eq!(Locale::Turkic.fold_case("DİYARBAKIR"), "diyarbakır");
+    /// ```
+    pub fn fold_case(&self, input: &str) -> String {
+        let mut buf = String::with_capacity(input.len());
+        let mut chars = input.chars().peekable();
+        while let Some(ch) = chars.next() {
+            match (self, ch) {
+                // 'I' followed by a combining dot above is the decomposed form of 'İ'
+                (Locale::Turkic, 'I') if chars.peek() == Some(&'\u{307}') => {
+                    chars.next();
+                    buf.push('i');
+                }
+                (Locale::Turkic, 'I') => buf.push('ı'),
+                (Locale::Turkic, 'İ') => buf.push('i'),
+                _ => fold(ch, &mut buf),
+            }
+        }
+        buf
+    }
+}
+
+/// Applies the full Unicode case folding to the character.
+fn fold(ch: char, buf: &mut String) {
+    match ch {
+        _ if ch.is_ascii() => buf.push(ch.to_ascii_lowercase()),
+        // The dotless i only folds to itself, as its uppercase is the 'I' of other languages
+        'ı' => buf.push(ch),
+        // Cherokee folds to uppercase, as its lowercase letters were added later
+        '\u{13A0}'..='\u{13F5}' => buf.push(ch),
+        '\u{13F8}'..='\u{13FD}' | '\u{AB70}'..='\u{ABBF}' => buf.extend(ch.to_uppercase()),
+        // Otherwise the folding is the lowercase of the uppercase, e.g. 'ß' becomes 'SS' and then
+        // 'ss'. Lowercasing first also folds 'ẞ', whose uppercase is itself, through 'ß'.
+        _ => buf.extend(
+            ch.to_lowercase()
+                .flat_map(char::to_uppercase)
+                .flat_map(char::to_lowercase),
+        ),
+    }
+}
+
+/// The letter case of a piece of text, used to carry the case of a matched text over to its
+/// replacement when the lowercase feature is disabled.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Case {
+    /// All letters are lowercase, e.g. 'what'.
+    Lower,
+    /// Only the first letter is uppercase, e.g. 'What'.
+    Title,
+    /// All letters are uppercase, e.g. 'WHAT'.
+    Upper,
+}
+
+impl Case {
+    /// Detects the case of the matched text. The byte following the match is used to disambiguate
+    /// single letter matches, e.g. the 'N' in 'Nice' is title case whereas in 'NICE' it is
+    /// uppercase.
+    pub(crate) fn detect(matched: &[u8], next: Option<&u8>) -> Case {
+        let mut letters = matched.iter().filter(|b| b.is_ascii_alphabetic());
+        let Some(first) = letters.next() else {
+            return Case::Lower;
+        };
+        if !first.is_ascii_uppercase() {
+            return Case::Lower;
+        }
+
+        let mut rest = letters.peekable();
+        if rest.peek().is_none() {
+            // Single letter, so look ahead to know if the whole word is uppercase
+            return match next {
+                Some(next) if next.is_ascii_uppercase() => Case::Upper,
+                _ => Case::Title,
+            };
+        }
+        if rest.all(|b| b.is_ascii_uppercase()) {
+            Case::Upper
+        } else {
+            Case::Title
+        }
+    }
+
+    /// Applies this case to the lowercase replacement.
+    pub(crate) fn apply<'a>(&self, replacement: &'a str) -> impl Iterator<Item = u8> + 'a {
+        let case = *self;
+        let mut capitalized = false;
+        replacement.bytes().map(move |b| match case {
+            Case::Lower => b,
+            Case::Upper => b.to_ascii_uppercase(),
+            Case::Title if !capitalized && b.is_ascii_alphabetic() => {
+                capitalized = true;
+                b.to_ascii_uppercase()
+            }
+            Case::Title => b,
+        })
+    }
+}
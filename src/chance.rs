@@ -0,0 +1,243 @@
+use alloc::format;
+use core::str::FromStr;
+
+use crate::UwuError;
+
+/// The resolution of the chances created from a probability or a percentage.
+const SCALE: u32 = 1_000_000;
+
+/// Specifies how often a feature like stutter or emojis is applied, either as 1 in a number of
+/// times, e.g. 1 in 4 words, or as a probability, e.g. 25% of words.
+///
+/// Chances are reduced to their simplest fraction, so `Chance::percent(25.0)` is the same as
+/// `Chance::one_in(4)` and produces the same output.
+///
+/// Example:
+/// ```
+/// use uwu_rs::{Chance, Uwu};
+///
+/// assert_eq!(Chance::percent(25.0).unwrap(), Chance::one_in(4));
+/// assert_eq!("0.25".parse::<Chance>().unwrap(), Chance::one_in(4));
+///
+/// let uwu = Uwu::builder().stutter_chance(Chance::percent(30.0).unwrap()).build();
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Chance {
+    numerator: u32,
+    denominator: u32,
+}
+
+impl Default for Chance {
+    fn default() -> Self {
+        Self::NEVER
+    }
+}
+
+impl Chance {
+    /// A chance of never applying a feature.
+    pub const NEVER: Self = Self {
+        numerator: 0,
+        denominator: 1,
+    };
+
+    /// A chance of always applying a feature.
+    pub const ALWAYS: Self = Self {
+        numerator: 1,
+        denominator: 1,
+    };
+
+    /// A chance of 1 in `n` times. A chance of 1 in 0 is invalid, and rejected when building an
+    /// [`crate::UwuEngine`].
+    pub const fn one_in(n: u8) -> Self {
+        Self {
+            numerator: 1,
+            denominator: n as u32,
+        }
+    }
+
+    /// A chance from a probability between 0 and 1, with a resolution of one in a million.
+    pub fn probability(probability: f64) -> Result<Self, UwuError> {
+        if !(0.0..=1.0).contains(&probability) {
+            return Err(UwuError::InvalidConfig(format!(
+                "probability must be between 0 and 1, got {probability}"
+            )));
+        }
+        // `f64::round` is not available without `std`
+        let numerator = (probability * SCALE as f64 + 0.5) as u32;
+        Ok(Self::ratio(numerator, SCALE))
+    }
+
+    /// A chance from a percentage between 0 and 100, with a resolution of one in a million.
+    pub fn percent(percent: f64) -> Result<Self, UwuError> {
+        if !(0.0..=100.0).contains(&percent) {
+            return Err(UwuError::InvalidConfig(format!(
+                "percentage must be between 0 and 100, got {percent}"
+            )));
+        }
+        Self::probability(percent / 100.0)
+    }
+
+    /// The probability of applying a feature, between 0 and 1. A chance of 1 in 0 returns infinity.
+    pub fn as_probability(&self) -> f64 {
+        f64::from(self.numerator) / f64::from(self.denominator)
+    }
+
+    /// Validates the chance of an enabled feature, naming the field in the error.
+    pub(crate) fn validate(&self, field: &str, feature: &str) -> Result<(), UwuError> {
+        if self.denominator == 0 {
+            return Err(UwuError::InvalidConfig(format!(
+                "{field} must be at least 1 in 1 when {feature} enabled, got 1 in 0"
+            )));
+        }
+        if self.numerator == 0 {
+            return Err(UwuError::InvalidConfig(format!(
+                "{field} must be greater than 0 when {feature} enabled"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Draws whether to apply the feature. A chance of 1 in N draws exactly like `rng.u8(0..N)`, so
+    /// the output of the same seed never changes.
+    #[inline(always)]
+    pub(crate) fn draw(&self, rng: &mut fastrand::Rng) -> bool {
+        rng.u32(0..self.denominator) < self.numerator
+    }
+
+    fn ratio(numerator: u32, denominator: u32) -> Self {
+        let divisor = gcd(numerator, denominator);
+        Self {
+            numerator: numerator / divisor,
+            denominator: denominator / divisor,
+        }
+    }
+}
+
+impl From<u8> for Chance {
+    fn from(n: u8) -> Self {
+        Self::one_in(n)
+    }
+}
+
+/// Parses a chance of 1 in a number of times, e.g. `4`, a probability, e.g. `0.25`, or a
+/// percentage, e.g. `25%`.
+impl FromStr for Chance {
+    type Err = UwuError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(percent) = s.strip_suffix('%') {
+            return match percent.trim_end().parse() {
+                Ok(percent) => Self::percent(percent),
+                Err(_) => Err(UwuError::InvalidConfig(format!("invalid percentage '{s}'"))),
+            };
+        }
+        if let Ok(n) = s.parse() {
+            return Ok(Self::one_in(n));
+        }
+        match s.parse() {
+            Ok(probability) => Self::probability(probability),
+            Err(_) => Err(UwuError::InvalidConfig(format!(
+                "invalid chance '{s}', expected 1 in N times, a probability or a percentage"
+            ))),
+        }
+    }
+}
+
+fn gcd(mut a: u32, mut b: u32) -> u32 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a.max(1)
+}
+
+/// Chances of 1 in N times are serialized as N, and the others as a probability.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Chance {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match u8::try_from(self.denominator) {
+            Ok(n) if self.numerator == 1 => serializer.serialize_u8(n),
+            _ => serializer.serialize_f64(self.as_probability()),
+        }
+    }
+}
+
+/// Chances are deserialized from an integer of 1 in N times, a float probability, or a string
+/// parsed like [`Chance::from_str`].
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Chance {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::{Error, Unexpected, Visitor};
+
+        struct ChanceVisitor;
+
+        impl Visitor<'_> for ChanceVisitor {
+            type Value = Chance;
+
+            fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                f.write_str("1 in N times, a probability or a percentage")
+            }
+
+            fn visit_u64<E: Error>(self, n: u64) -> Result<Chance, E> {
+                u8::try_from(n)
+                    .map(Chance::one_in)
+                    .map_err(|_| E::invalid_value(Unexpected::Unsigned(n), &"1 in at most 255"))
+            }
+
+            fn visit_i64<E: Error>(self, n: i64) -> Result<Chance, E> {
+                u8::try_from(n)
+                    .map(Chance::one_in)
+                    .map_err(|_| E::invalid_value(Unexpected::Signed(n), &"1 in at most 255"))
+            }
+
+            fn visit_f64<E: Error>(self, probability: f64) -> Result<Chance, E> {
+                Chance::probability(probability).map_err(E::custom)
+            }
+
+            fn visit_str<E: Error>(self, s: &str) -> Result<Chance, E> {
+                s.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(ChanceVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Uwu;
+
+    #[test]
+    fn assert_chance() {
+        assert_eq!(Chance::probability(0.5).unwrap(), Chance::one_in(2));
+        assert_eq!(Chance::percent(100.0).unwrap(), Chance::ALWAYS);
+        assert_eq!(Chance::probability(0.0).unwrap(), Chance::NEVER);
+        assert_eq!(Chance::percent(12.5).unwrap().as_probability(), 0.125);
+        assert!(Chance::probability(1.5).is_err());
+        assert!(Chance::probability(f64::NAN).is_err());
+        assert!(Chance::percent(-1.0).is_err());
+
+        assert_eq!("4".parse::<Chance>().unwrap(), Chance::one_in(4));
+        assert_eq!(" 25 % ".parse::<Chance>().unwrap(), Chance::one_in(4));
+        assert_eq!("0.3".parse::<Chance>().unwrap().as_probability(), 0.3);
+        assert!("often".parse::<Chance>().is_err());
+        assert!("300".parse::<Chance>().is_err());
+
+        // A chance of 1 in N draws like before, so the same seed produces the same output
+        let input = "the quick brown fox jumps over the lazy dog. the end.";
+        let one_in = Uwu::builder().stutter(3).emojis(2).build();
+        let probability = Uwu::builder()
+            .stutter(3)
+            .emojis_chance(Chance::probability(0.5).unwrap())
+            .build();
+        assert_eq!(
+            one_in.uwuify(input).unwrap(),
+            probability.uwuify(input).unwrap()
+        );
+        let uwu = Uwu::builder()
+            .stutter_chance(Chance::percent(30.0).unwrap())
+            .build();
+        assert_ne!(uwu.uwuify(input).unwrap(), input);
+    }
+}
@@ -0,0 +1,285 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Chance, Dictionary, EmojiSet, Locale, Protection, PunctuationSet, Seed, StutterOptions, Uwu,
+    UwuBuilder, UwuError,
+};
+
+/// The version of the serialized configuration, increased whenever a field changes its meaning.
+///
+/// Version 2 added the nya feature, which replaced the ' n' expression of version 1.
+const VERSION: u32 = 2;
+
+/// The serialized configuration of [`Uwu`] and [`UwuBuilder`].
+///
+/// Field names are part of the format, so they must never change within a version. Missing fields
+/// keep the default value of the type being deserialized, i.e. `Uwu::default()` for `Uwu`, or a
+/// disabled feature for `UwuBuilder`.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct UwuConfig {
+    #[serde(default = "version")]
+    version: u32,
+    lowercase: Option<bool>,
+    locale: Option<Locale>,
+    expressions: Option<bool>,
+    dictionary: Option<Dictionary>,
+    nya: Option<bool>,
+    nya_exceptions: Option<Vec<String>>,
+    w_replace: Option<bool>,
+    stutter: Option<bool>,
+    stutter_chance: Option<Chance>,
+    stutter_options: Option<StutterOptions>,
+    emojis: Option<bool>,
+    emojis_chance: Option<Chance>,
+    emoji_set: Option<EmojiSet>,
+    punctuation_set: Option<PunctuationSet>,
+    protection: Option<Protection>,
+    protected_words: Option<Vec<String>>,
+    seed: Option<Seed>,
+}
+
+fn version() -> u32 {
+    VERSION
+}
+
+impl UwuConfig {
+    /// Applies the configuration on top of the base, validating the result.
+    fn apply(mut self, base: Uwu) -> Result<Uwu, UwuError> {
+        if self.version > VERSION {
+            return Err(UwuError::InvalidConfig(format!(
+                "unsupported version {}, the latest is {VERSION}",
+                self.version
+            )));
+        }
+        if self.version < 2 {
+            self.migrate_v1(&base);
+        }
+
+        let uwu = Uwu {
+            lowercase: self.lowercase.unwrap_or(base.lowercase),
+            locale: self.locale.unwrap_or(base.locale),
+            expressions: self.expressions.unwrap_or(base.expressions),
+            dictionary: self.dictionary.unwrap_or(base.dictionary),
+            nya: self.nya.unwrap_or(base.nya),
+            nya_exceptions: self.nya_exceptions.unwrap_or(base.nya_exceptions),
+            w_replace: self.w_replace.unwrap_or(base.w_replace),
+            stutter: self.stutter.unwrap_or(base.stutter),
+            stutter_chance: self.stutter_chance.unwrap_or(base.stutter_chance),
+            stutter_options: self.stutter_options.unwrap_or(base.stutter_options),
+            emojis: self.emojis.unwrap_or(base.emojis),
+            emojis_chance: self.emojis_chance.unwrap_or(base.emojis_chance),
+            emoji_set: self.emoji_set.unwrap_or(base.emoji_set),
+            punctuation_set: self.punctuation_set.unwrap_or(base.punctuation_set),
+            protection: self.protection.unwrap_or(base.protection),
+            protected_words: self.protected_words.unwrap_or(base.protected_words),
+            seed: self.seed.unwrap_or(base.seed),
+        };
+        uwu.validate()?;
+        Ok(uwu)
+    }
+
+    /// Migrates a version 1 configuration, where the expressions feature added the nya with the
+    /// ' n' expression, e.g. 'nice' became 'nyice'.
+    fn migrate_v1(&mut self, base: &Uwu) {
+        let expressions = self.expressions.unwrap_or(base.expressions);
+        self.nya.get_or_insert(expressions);
+        if let Some(dictionary) = &mut self.dictionary {
+            if dictionary.get(" n") == Some(" ny") {
+                dictionary.remove(" n");
+            }
+        }
+    }
+}
+
+impl From<Uwu> for UwuConfig {
+    fn from(uwu: Uwu) -> Self {
+        Self {
+            version: VERSION,
+            lowercase: Some(uwu.lowercase),
+            locale: Some(uwu.locale),
+            expressions: Some(uwu.expressions),
+            dictionary: Some(uwu.dictionary),
+            nya: Some(uwu.nya),
+            nya_exceptions: Some(uwu.nya_exceptions),
+            w_replace: Some(uwu.w_replace),
+            stutter: Some(uwu.stutter),
+            stutter_chance: Some(uwu.stutter_chance),
+            stutter_options: Some(uwu.stutter_options),
+            emojis: Some(uwu.emojis),
+            emojis_chance: Some(uwu.emojis_chance),
+            emoji_set: Some(uwu.emoji_set),
+            punctuation_set: Some(uwu.punctuation_set),
+            protection: Some(uwu.protection),
+            protected_words: Some(uwu.protected_words),
+            seed: Some(uwu.seed),
+        }
+    }
+}
+
+impl From<UwuBuilder> for UwuConfig {
+    fn from(builder: UwuBuilder) -> Self {
+        Self::from(builder.build())
+    }
+}
+
+impl TryFrom<UwuConfig> for Uwu {
+    type Error = UwuError;
+
+    fn try_from(config: UwuConfig) -> Result<Self, Self::Error> {
+        config.apply(Uwu::default())
+    }
+}
+
+impl TryFrom<UwuConfig> for UwuBuilder {
+    type Error = UwuError;
+
+    fn try_from(config: UwuConfig) -> Result<Self, Self::Error> {
+        config
+            .apply(UwuBuilder::default().build())
+            .map(|uwu| UwuBuilder::from(&uwu))
+    }
+}
+
+#[cfg(all(test, any(feature = "json", feature = "toml")))]
+mod tests {
+    use crate::Uwu;
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn assert_json_config() {
+        use crate::{Expression, UwuBuilder};
+
+        let uwu = Uwu::builder()
+            .lowercase()
+            .expressions()
+            .insert_expression(Expression::new("hi", "hai").match_prefix())
+            .stutter(2)
+            .stutter_repeats(2, crate::Chance::percent(10.0).unwrap())
+            .add_stutter_stopword("the")
+            .add_nya_exception("anime")
+            .add_emoji("OwO ", 3)
+            .protect_urls()
+            .protect_word("Rust")
+            .seed(crate::Seed::Input)
+            .build();
+        let json = serde_json::to_string(&uwu).unwrap();
+        assert!(json.starts_with(r#"{"version":2,"lowercase":true,"locale":"root","#));
+        assert_eq!(serde_json::from_str::<Uwu>(&json).unwrap(), uwu);
+
+        let builder = serde_json::from_str::<UwuBuilder>(&json).unwrap();
+        assert_eq!(builder.build(), uwu);
+
+        // Missing fields keep their default value
+        let uwu: Uwu = serde_json::from_str(r#"{"stutter_chance": 2}"#).unwrap();
+        assert_eq!(uwu, UwuBuilder::from(&Uwu::default()).stutter(2).build());
+        let uwu: Uwu = serde_json::from_str(r#"{"emojis_chance": "25%"}"#).unwrap();
+        assert_eq!(uwu.emojis_chance, crate::Chance::one_in(4));
+        let uwu: Uwu = serde_json::from_str(r#"{"stutter_chance": 0.3}"#).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Uwu>(&serde_json::to_string(&uwu).unwrap()).unwrap(),
+            uwu
+        );
+        let uwu: Uwu = serde_json::from_str(r#"{"nya": false}"#).unwrap();
+        assert!(!uwu.nya && uwu.expressions);
+        let builder: UwuBuilder = serde_json::from_str(r#"{"w_replace": true}"#).unwrap();
+        assert_eq!(builder, UwuBuilder::new().w_replace().clone());
+
+        for invalid in [
+            r#"{"version": 3}"#,
+            r#"{"stutter_chance": 0}"#,
+            r#"{"emojis": true, "emojis_chance": 0}"#,
+            r#"{"protected_words": ["two words"]}"#,
+            r#"{"locale": "klingon"}"#,
+            r#"{"stutter_chance": 1.5}"#,
+            r#"{"stutter_chance": 300}"#,
+            r#"{"stutter_options": {"max_repeats": 0}}"#,
+            r#"{"stutter_options": {"mode": {"every-nth": 0}}}"#,
+        ] {
+            assert!(serde_json::from_str::<Uwu>(invalid).is_err(), "{invalid}");
+        }
+        // Disabled features may have a chance of 0, like the default builder
+        assert!(serde_json::from_str::<UwuBuilder>(r#"{"stutter_chance": 0}"#).is_ok());
+    }
+
+    //noinspection SpellCheckingInspection
+    #[cfg(feature = "json")]
+    #[test]
+    fn assert_json_config_v1() {
+        use crate::UwuBuilder;
+
+        // Version 1 added the nya with the ' n' expression, so it follows the expressions feature
+        let v1 = r#"{
+            "version": 1,
+            "stutter": false,
+            "emojis": false,
+            "dictionary": {
+                "match_kind": "leftmost-longest",
+                "expressions": [
+                    {"pattern": " n", "replacement": " ny", "prefix": true},
+                    {"pattern": "what", "replacement": "nani"}
+                ]
+            }
+        }"#;
+        let uwu: Uwu = serde_json::from_str(v1).unwrap();
+        assert!(uwu.nya);
+        assert_eq!(uwu.dictionary.get(" n"), None);
+        assert_eq!(
+            uwu.uwuify("What a nice anime").unwrap(),
+            "nani a nyice anyime"
+        );
+
+        let json = serde_json::to_string(&uwu).unwrap();
+        assert!(json.starts_with(r#"{"version":2,"#));
+        assert_eq!(serde_json::from_str::<Uwu>(&json).unwrap(), uwu);
+
+        let uwu: Uwu = serde_json::from_str(r#"{"version": 1, "expressions": false}"#).unwrap();
+        assert!(!uwu.nya);
+        let builder: UwuBuilder =
+            serde_json::from_str(r#"{"version": 1, "expressions": true}"#).unwrap();
+        assert!(builder.nya);
+        // Version 2 keeps the nya of the base, and the dictionary as is
+        let uwu: Uwu = serde_json::from_str(r#"{"version": 2, "expressions": false}"#).unwrap();
+        assert!(uwu.nya);
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn assert_toml_config() {
+        let uwu: Uwu = toml::from_str(
+            r#"
+            version = 2
+            lowercase = false
+            protected_words = ["Ferris"]
+            nya_exceptions = ["anime"]
+
+            [seed]
+            fixed = 42
+
+            [protection]
+            urls = true
+
+            [stutter_options]
+            min_length = 2
+            stopwords = ["the"]
+            mode = { every-nth = 3 }
+
+            [punctuation_set]
+            punctuation = [{ text = ";" }, { text = "。", standalone = true }]
+            "#,
+        )
+        .unwrap();
+        assert!(!uwu.lowercase && uwu.expressions);
+        assert_eq!(uwu.seed, crate::Seed::Fixed(42));
+        assert!(uwu.protection.urls && !uwu.protection.emails);
+        assert_eq!(uwu.punctuation_set.len(), 2);
+        assert_eq!(uwu.stutter_options.min_length, 2);
+        assert_eq!(uwu.stutter_options.max_repeats, 1);
+        assert_eq!(uwu.stutter_options.mode, crate::StutterMode::EveryNth(3));
+        assert_eq!(uwu.uwuify("Ferris rules").unwrap(), "Ferris wuwes");
+        assert_eq!(uwu.uwuify("Anime nerd").unwrap(), "Anime nyewd");
+    }
+}
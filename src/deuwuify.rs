@@ -0,0 +1,498 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use aho_corasick::{AhoCorasick, MatchKind};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::case::Case;
+use crate::dictionary::BOUNDARY;
+use crate::tokenizer::is_grapheme_boundary;
+use crate::{dict, pipeline, Uwu, UwuError};
+
+/// The confidence of a word restored by reversing an expression, since the replacement may also be
+/// a genuine word, e.g. 'smol'.
+const EXPRESSION_CONFIDENCE: f32 = 0.9;
+/// The confidence of a word with a 'w' that does not match any known word.
+const UNKNOWN_CONFIDENCE: f32 = 0.25;
+/// The maximum number of 'w's resolved in a word, since each of them may be a 'w', 'l' or 'r'.
+const MAX_AMBIGUOUS: usize = 6;
+
+/// Converts uwuified text back into a best-effort plain version, e.g. for moderation or search.
+///
+/// It strips the emojis, removes the stutter and the nya, reverses the expressions and uses a list
+/// of known words to resolve each 'w' back to a 'w', 'l' or 'r'. Lowercased text cannot be restored.
+///
+/// Example:
+/// ```
+/// use uwu_rs::{Deuwuifier, Uwu};
+///
+/// let uwu = Uwu::new();
+/// let uwuified = uwu.uwuify("What a lovely world!").unwrap();
+///
+/// let deuwuified = Deuwuifier::new(&uwu).unwrap().deuwuify(&uwuified);
+/// assert_eq!(deuwuified.text, "what a lovely world!");
+/// assert_eq!(deuwuified.words[0].uwuified, "nani");
+/// assert_eq!(deuwuified.words[0].restored, "what");
+/// ```
+#[derive(Clone, Debug)]
+pub struct Deuwuifier {
+    emojis: AhoCorasick,
+    /// Whether each emoji ends with a space, by pattern ID.
+    emojis_spaced: Vec<bool>,
+    /// The expressions that replace whole words, by their lowercase replacement.
+    expressions: BTreeMap<String, Reversal>,
+    /// The expressions that replace the start or end of words.
+    affixes: Vec<Affix>,
+    /// The known words, with their rank, lower being more common.
+    words: BTreeMap<String, usize>,
+    /// Whether the 'y' after an 'n' followed by a vowel is removed, e.g. 'nyice'.
+    nya: bool,
+}
+
+#[derive(Clone, Debug)]
+struct Reversal {
+    pattern: String,
+    /// The punctuation that ends the replacement, e.g. the '~' in 'kawaii~'.
+    tail: String,
+}
+
+#[derive(Clone, Debug)]
+struct Affix {
+    replacement: String,
+    pattern: String,
+    prefix: bool,
+    suffix: bool,
+}
+
+/// The text restored by a [`Deuwuifier`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Deuwuified {
+    /// The restored text.
+    pub text: String,
+    /// Every restored word, in order.
+    pub words: Vec<DeuwuifiedWord>,
+}
+
+/// A word restored by a [`Deuwuifier`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeuwuifiedWord {
+    /// The word as found in the uwuified text, e.g. 'h-hewwo'.
+    pub uwuified: String,
+    /// The restored word, e.g. 'hello'.
+    pub restored: String,
+    /// How likely the restored word is to be right, from 0 to 1. Words with a 'w' that match
+    /// multiple known words get a lower confidence, e.g. 'wight' may be 'light' or 'right'.
+    pub confidence: f32,
+}
+
+/// A word with every 'w' resolved.
+struct Resolved {
+    text: String,
+    confidence: f32,
+    known: bool,
+}
+
+impl Deuwuifier {
+    /// Creates a new deuwuifier that reverses the emojis and expressions of the `Uwu` instance,
+    /// with the default list of known words.
+    pub fn new(uwu: &Uwu) -> Result<Self, UwuError> {
+        let emojis = AhoCorasick::builder()
+            .match_kind(MatchKind::LeftmostLongest)
+            .build(uwu.emoji_set.iter().map(|emoji| emoji.text.trim_end()))?;
+        let emojis_spaced = uwu
+            .emoji_set
+            .iter()
+            .map(|emoji| emoji.text.ends_with(' '))
+            .collect();
+
+        let mut expressions = BTreeMap::new();
+        let mut affixes = Vec::new();
+        let dictionary = uwu.dictionary.iter().collect::<Vec<_>>();
+        for form in uwu.dictionary.forms() {
+            let expression = dictionary[form.expression];
+            if expression.replacement.starts_with(' ') && !form.replacement.starts_with(' ') {
+                // Legacy patterns after other boundaries, e.g. '(q', restore like ' q'
+                continue;
+            }
+            let pattern = form
+                .pattern
+                .iter()
+                .filter(|byte| **byte != BOUNDARY)
+                .map(|byte| *byte as char)
+                .collect::<String>();
+            // Legacy patterns start with a space, e.g. ' n'
+            let pattern = pattern.trim_start().to_lowercase();
+            let replacement = form.replacement.trim_start().to_lowercase();
+            let key = replacement.trim_end_matches(|char: char| !char.is_alphanumeric());
+            if pattern.is_empty() || key.is_empty() {
+                continue;
+            }
+
+            // The replacement is w-replaced after the expressions, e.g. 'luv' becomes 'wuv'
+            let replaced = key.bytes().map(pipeline::w_replace).collect::<Vec<_>>();
+            let replaced = String::from_utf8(replaced).unwrap_or_default();
+            for key in [key, &replaced] {
+                if expression.prefix || expression.suffix {
+                    affixes.push(Affix {
+                        replacement: key.to_string(),
+                        pattern: pattern.clone(),
+                        prefix: expression.prefix,
+                        suffix: expression.suffix,
+                    });
+                } else {
+                    expressions
+                        .entry(key.to_string())
+                        .or_insert_with(|| Reversal {
+                            pattern: pattern.clone(),
+                            tail: replacement[key.len()..].to_string(),
+                        });
+                }
+            }
+        }
+
+        let mut deuwuifier = Self {
+            emojis,
+            emojis_spaced,
+            expressions,
+            affixes,
+            words: BTreeMap::new(),
+            nya: uwu.nya,
+        };
+        for word in dict::WORDS {
+            deuwuifier.insert_word(word);
+        }
+        Ok(deuwuifier)
+    }
+
+    /// Adds a known word, less common than the known words added before it.
+    pub fn insert_word<T: AsRef<str>>(&mut self, word: T) -> &mut Self {
+        let rank = self.words.len();
+        self.words
+            .entry(word.as_ref().to_lowercase())
+            .or_insert(rank);
+        self
+    }
+
+    /// Removes every known word.
+    pub fn clear_words(&mut self) -> &mut Self {
+        self.words.clear();
+        self
+    }
+
+    /// Converts the uwuified text back into a best-effort plain version.
+    pub fn deuwuify<S: AsRef<str>>(&self, input: S) -> Deuwuified {
+        let text = self.strip_emojis(input.as_ref());
+
+        let mut output = String::with_capacity(text.len());
+        let mut words = Vec::new();
+        for token in text.split_inclusive(char::is_whitespace) {
+            let token_end = token.trim_end_matches(char::is_whitespace).len();
+            let (token, whitespace) = token.split_at(token_end);
+            let core = token.trim_matches(|char: char| !char.is_alphanumeric());
+            if core.is_empty() {
+                output.push_str(token);
+                output.push_str(whitespace);
+                continue;
+            }
+
+            let leading = &token[..token.find(core).unwrap_or_default()];
+            let mut trailing = &token[leading.len() + core.len()..];
+            let restored = self.restore(core, &mut trailing);
+            output.push_str(leading);
+            output.push_str(&restored.text);
+            output.push_str(trailing);
+            output.push_str(whitespace);
+            words.push(DeuwuifiedWord {
+                uwuified: core.to_string(),
+                restored: restored.text,
+                confidence: restored.confidence,
+            });
+        }
+
+        Deuwuified {
+            text: output,
+            words,
+        }
+    }
+
+    /// Removes the emojis that follow whitespace or punctuation, along with their space.
+    fn strip_emojis(&self, input: &str) -> String {
+        let mut output = String::with_capacity(input.len());
+        let mut copied = 0;
+        for mat in self.emojis.find_iter(input) {
+            let before = input[copied..mat.start()].chars().next_back();
+            if before.is_some_and(char::is_alphanumeric) {
+                continue;
+            }
+            let spaced = self.emojis_spaced[mat.pattern().as_usize()];
+            let after = input[mat.end()..].chars().next();
+            let range = if after == Some(' ') && spaced {
+                // The emoji was added after a space, with its own space
+                mat.start()..mat.end() + 1
+            } else if after.is_none_or(char::is_whitespace) {
+                // The emoji was added before other whitespace, with a space before it
+                match before {
+                    Some(' ') => mat.start() - 1..mat.end(),
+                    _ => mat.range(),
+                }
+            } else if !spaced {
+                mat.range()
+            } else {
+                continue;
+            };
+            output.push_str(&input[copied..range.start]);
+            copied = range.end;
+        }
+        output.push_str(&input[copied..]);
+        output
+    }
+
+    /// Restores a word, consuming the punctuation that ends a reversed expression, if any.
+    fn restore(&self, word: &str, trailing: &mut &str) -> Resolved {
+        // Remove the stutter, e.g. 'h-hello'
+        let mut word = word;
+        while let Some(rest) = strip_stutter(word) {
+            word = rest;
+        }
+
+        // Genuine words may have an 'ny' followed by a vowel, e.g. 'canyon'
+        if let Some(plain) = self.nya.then(|| remove_nya(word)).flatten() {
+            let mut plain_trailing = *trailing;
+            let restored = self.restore_plain(&plain, &mut plain_trailing);
+            if restored.known {
+                *trailing = plain_trailing;
+                return restored;
+            }
+            let original = self.restore_plain(word, trailing);
+            if original.known {
+                return original;
+            }
+            return Resolved {
+                confidence: UNKNOWN_CONFIDENCE,
+                ..restored
+            };
+        }
+        self.restore_plain(word, trailing)
+    }
+
+    /// Restores a word without stutter or nya, consuming the punctuation that ends a reversed
+    /// expression, if any.
+    fn restore_plain(&self, word: &str, trailing: &mut &str) -> Resolved {
+        let lowercase = word.to_lowercase();
+        if let Some(reversal) = self.expressions.get(&lowercase) {
+            if let Some(rest) = trailing.strip_prefix(reversal.tail.as_str()) {
+                *trailing = rest;
+            }
+            return Resolved {
+                text: with_case(word.as_bytes(), None, &reversal.pattern),
+                confidence: EXPRESSION_CONFIDENCE,
+                known: true,
+            };
+        }
+
+        let mut resolved = self.resolve(word);
+        for affix in &self.affixes {
+            let len = affix.replacement.len();
+            if lowercase.len() <= len {
+                continue;
+            }
+            let candidate = if affix.prefix && lowercase.starts_with(&affix.replacement) {
+                let case = with_case(
+                    &word.as_bytes()[..len],
+                    word.as_bytes().get(len),
+                    &affix.pattern,
+                );
+                format!("{case}{}", &word[len..])
+            } else if affix.suffix && lowercase.ends_with(&affix.replacement) {
+                let start = word.len() - len;
+                let case = with_case(&word.as_bytes()[start..], None, &affix.pattern);
+                format!("{}{case}", &word[..start])
+            } else {
+                continue;
+            };
+
+            // Genuine words may start or end like a replacement, e.g. 'nylon'
+            let reversed = self.resolve(&candidate);
+            if reversed.known || !resolved.known {
+                let confidence = match reversed.known {
+                    true => reversed.confidence * EXPRESSION_CONFIDENCE,
+                    false => UNKNOWN_CONFIDENCE,
+                };
+                resolved = Resolved {
+                    confidence,
+                    ..reversed
+                };
+                break;
+            }
+        }
+        resolved
+    }
+
+    /// Resolves every 'w' of the word to the most common known word.
+    fn resolve(&self, word: &str) -> Resolved {
+        let lowercase = word.to_lowercase();
+        let ambiguous = lowercase
+            .bytes()
+            .enumerate()
+            .filter(|(_, byte)| *byte == b'w')
+            .map(|(idx, _)| idx)
+            .collect::<Vec<_>>();
+        if ambiguous.is_empty() {
+            return Resolved {
+                text: word.to_string(),
+                confidence: 1.0,
+                known: self.words.contains_key(&lowercase),
+            };
+        }
+
+        let mut best: Option<(usize, String)> = None;
+        let mut matches = 0;
+        if ambiguous.len() <= MAX_AMBIGUOUS {
+            let mut candidate = lowercase.clone().into_bytes();
+            for mut combination in 0..3usize.pow(ambiguous.len() as u32) {
+                for idx in &ambiguous {
+                    candidate[*idx] = b"wlr"[combination % 3];
+                    combination /= 3;
+                }
+                let candidate = core::str::from_utf8(&candidate).unwrap_or_default();
+                if let Some(rank) = self.words.get(candidate) {
+                    matches += 1;
+                    if best.as_ref().is_none_or(|(best, _)| rank < best) {
+                        best = Some((*rank, candidate.to_string()));
+                    }
+                }
+            }
+        }
+
+        match best {
+            Some((_, candidate)) if candidate.len() == word.len() => {
+                // Only ASCII letters changed, so the case can be copied byte by byte
+                let text = word
+                    .bytes()
+                    .zip(candidate.bytes())
+                    .map(|(original, resolved)| match original.is_ascii_uppercase() {
+                        true => resolved.to_ascii_uppercase(),
+                        false => resolved,
+                    })
+                    .collect::<Vec<_>>();
+                Resolved {
+                    text: String::from_utf8(text).unwrap_or(candidate),
+                    confidence: 1.0 / matches as f32,
+                    known: true,
+                }
+            }
+            _ => Resolved {
+                text: word.to_string(),
+                confidence: UNKNOWN_CONFIDENCE,
+                known: false,
+            },
+        }
+    }
+}
+
+/// Applies the case of the matched text to the lowercase pattern.
+fn with_case(matched: &[u8], next: Option<&u8>, pattern: &str) -> String {
+    let bytes = Case::detect(matched, next)
+        .apply(pattern)
+        .collect::<Vec<_>>();
+    String::from_utf8(bytes).unwrap_or_else(|_| pattern.to_string())
+}
+
+/// Removes the 'y' between every 'n' and vowel of the word, e.g. 'nyice' becomes 'nice'. Returns
+/// `None` if the word has no such 'y'.
+fn remove_nya(word: &str) -> Option<String> {
+    let bytes = word.as_bytes();
+    let nya = |idx: usize| {
+        idx > 0
+            && bytes[idx].eq_ignore_ascii_case(&b'y')
+            && bytes[idx - 1].eq_ignore_ascii_case(&b'n')
+            && bytes
+                .get(idx + 1)
+                .is_some_and(|byte| pipeline::is_vowel(*byte))
+    };
+    if !(0..bytes.len()).any(nya) {
+        return None;
+    }
+    let plain = (0..bytes.len())
+        .filter(|idx| !nya(*idx))
+        .map(|idx| bytes[idx])
+        .collect::<Vec<_>>();
+    String::from_utf8(plain).ok()
+}
+
+/// Removes one stutter from the start of the word, e.g. 'h-hello' or 'st-stop' become 'hello' and
+/// 'stop', where the repeated letters are followed by the same grapheme boundary.
+fn strip_stutter(word: &str) -> Option<&str> {
+    let (prefix, rest) = word.split_once('-')?;
+    let letters = !prefix.is_empty()
+        && prefix
+            .graphemes(true)
+            .all(|grapheme| grapheme.starts_with(char::is_alphabetic));
+    let repeated = rest.starts_with(prefix) && is_grapheme_boundary(rest, prefix.len());
+    (letters && repeated).then_some(rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //noinspection SpellCheckingInspection
+    #[test]
+    fn assert_deuwuify() {
+        let uwu = Uwu::builder()
+            .expressions()
+            .nya()
+            .w_replace()
+            .stutter(2)
+            .emojis(1)
+            .build();
+        let deuwuifier = Deuwuifier::new(&uwu).unwrap();
+
+        let input = "Hello world! What a lovely day. I really love the small fluffy cat, not you.";
+        let uwuified = uwu.uwuify(input).unwrap();
+        assert_ne!(uwuified, input);
+        assert_eq!(deuwuifier.deuwuify(&uwuified).text, input);
+
+        let deuwuified = deuwuifier.deuwuify("W-Wight, nyice kawaii~ wuv! OwO\nbye");
+        assert_eq!(deuwuified.text, "Right, nice cute love!\nbye");
+        let words = deuwuified
+            .words
+            .iter()
+            .map(|word| {
+                (
+                    word.uwuified.as_str(),
+                    word.restored.as_str(),
+                    word.confidence,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            words,
+            [
+                ("W-Wight", "Right", 0.5),
+                ("nyice", "nice", UNKNOWN_CONFIDENCE),
+                ("kawaii", "cute", EXPRESSION_CONFIDENCE),
+                ("wuv", "love", EXPRESSION_CONFIDENCE),
+                ("bye", "bye", 1.0),
+            ]
+        );
+
+        let mut deuwuifier = deuwuifier;
+        deuwuifier.insert_word("nice").insert_word("nylon");
+        let deuwuified = deuwuifier.deuwuify("nyice nylon, twins");
+        assert_eq!(deuwuified.text, "nice nylon, twins");
+        assert_eq!(deuwuified.words[0].confidence, 1.0);
+        assert_eq!(deuwuified.words[1].confidence, 1.0);
+        assert_eq!(deuwuified.words[2].confidence, UNKNOWN_CONFIDENCE);
+
+        // The stutter of non-ASCII words repeats their first grapheme
+        let deuwuified = deuwuifier.deuwuify("é-élan e\u{301}-e\u{301}tude п-привет x-y");
+        assert_eq!(deuwuified.text, "élan e\u{301}tude привет x-y");
+
+        // Repeated stutter and consonant clusters
+        let deuwuified = deuwuifier.deuwuify("h-h-h-hewwo St-Stop thw-thwough");
+        assert_eq!(deuwuified.text, "hello Stop through");
+    }
+}
@@ -1,11 +1,20 @@
-pub const EXPRESSIONS: [&str; 9] = [
-    " n", " q", "small", "cute", "fluff", "love", "stupid", "what", "meow",
+pub const EXPRESSIONS: [&str; 8] = [
+    " q", "small", "cute", "fluff", "love", "stupid", "what", "meow",
 ];
-pub const EXPRESSIONS_REPLACE: [&str; 9] = [
-    " ny", " qw", "smol", "kawaii~", "floof", "luv", "baka", "nani", "nya~",
+pub const EXPRESSIONS_REPLACE: [&str; 8] = [
+    " qw", "smol", "kawaii~", "floof", "luv", "baka", "nani", "nya~",
 ];
+pub const EXPRESSIONS_INFLECTED: [&str; 1] = ["love"];
 
-pub const PUNCTUATION: [&str; 3] = [", ", ". ", "! "];
+pub const INFLECTIONS: [&str; 5] = ["s", "es", "ed", "ing", "er"];
+
+pub const NYA_EXCEPTIONS: [&str; 11] = [
+    "one", "once", "done", "gone", "none", "alone", "someone", "anyone", "everyone", "phone",
+    "since",
+];
+
+pub const PUNCTUATION: [&str; 5] = [",", ".", "!", "?", "…"];
+pub const PUNCTUATION_STANDALONE: [&str; 3] = ["。", "！", "？"];
 
 pub const EMOJIS: [&str; 32] = [
     "rawr x3 ",
@@ -41,3 +50,32 @@ pub const EMOJIS: [&str; 32] = [
     "👉👈",
     "*blushes*",
 ];
+
+/// Common English words, by frequency, used to resolve a 'w' back to 'l' or 'r'. Only words with
+/// an 'l', 'r' or 'w' are relevant.
+pub const WORDS: [&str; 257] = [
+    "we", "will", "would", "all", "her", "were", "there", "their", "or", "from", "what", "out",
+    "more", "your", "if", "other", "who", "like", "new", "year", "work", "only", "people", "world",
+    "now", "how", "also", "well", "first", "over", "after", "where", "way", "very", "really",
+    "through", "our", "three", "great", "before", "right", "old", "life", "long", "little", "want",
+    "still", "last", "never", "number", "part", "own", "place", "large", "small", "real", "read",
+    "write", "learn", "love", "lovely", "loved", "loves", "loving", "lover", "look", "looks",
+    "help", "hello", "call", "called", "later", "play", "plays", "player", "light", "left", "line",
+    "live", "lives", "lot", "low", "lower", "letter", "level", "less", "list", "lost", "let",
+    "leave", "lead", "law", "land", "language", "late", "least", "local", "rule", "rules", "run",
+    "room", "road", "rest", "result", "reason", "return", "report", "red", "rather", "ready",
+    "remember", "around", "are", "car", "care", "cry", "dear", "door", "dream", "dry", "early",
+    "ear", "every", "far", "fire", "for", "form", "free", "friend", "friends", "fur", "girl",
+    "girls", "good", "grow", "ground", "group", "hard", "hear", "here", "hour", "hurry", "morning",
+    "mother", "father", "brother", "sister", "water", "word", "words", "wrong", "war", "warm",
+    "wall", "walk", "watch", "weird", "wet", "why", "with", "without", "wow", "was", "when",
+    "which", "while", "white", "wide", "wife", "win", "wind", "window", "wish", "woman", "women",
+    "wonder", "wood", "week", "worry", "worse", "worst", "worth", "yellow", "yes", "follow",
+    "allow", "below", "blow", "blue", "black", "blood", "bread", "break", "bring", "brown",
+    "clear", "close", "cold", "color", "could", "should", "class", "clean", "cool", "cloud",
+    "field", "fly", "full", "feel", "floor", "flower", "fluffy", "glad", "glass", "glove", "hold",
+    "hole", "ill", "island", "kill", "lake", "lazy", "lip", "ball", "bell", "build", "child",
+    "children", "fall", "fill", "hall", "hill", "mile", "milk", "sell", "sleep", "slow", "smile",
+    "soul", "spell", "tell", "tall", "tool", "until", "usual", "know", "show", "down", "two",
+    "away", "always", "power", "answer", "between", "sweet", "snow", "twin",
+];
@@ -0,0 +1,772 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use crate::pipeline::STUTTER_BOUNDARIES;
+use crate::{dict, UwuError};
+
+/// Specifies which expression is replaced when the patterns of multiple expressions match
+/// overlapping text.
+///
+/// Example:
+/// ```
+/// use uwu_rs::{MatchKind, Uwu};
+///
+/// use uwu_rs::Expression;
+///
+/// let uwu = Uwu::builder()
+///     .expressions()
+///     .insert_expression(Expression::new("hi", "hai").match_prefix())
+///     .add_expression("hiya", "hiya~")
+///     .expressions_match_kind(MatchKind::LeftmostLongest)
+///     .build();
+/// assert_eq!(uwu.uwuify("hiya").unwrap(), "hiya~");
+/// ```
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum MatchKind {
+    /// Replaces the expression that ends first, e.g. with 'hi' matching the start of words and
+    /// 'hiya', 'hiya' becomes 'haiya'.
+    #[default]
+    Standard,
+    /// Replaces the expression that starts first, preferring the one added first, e.g. with 'hi'
+    /// and 'hiya', 'hiya' becomes 'haiya'.
+    LeftmostFirst,
+    /// Replaces the expression that starts first, preferring the longest one, e.g. with 'hi' and
+    /// 'hiya', 'hiya' becomes 'hiya~'.
+    LeftmostLongest,
+}
+
+impl From<MatchKind> for aho_corasick::MatchKind {
+    fn from(kind: MatchKind) -> Self {
+        match kind {
+            MatchKind::Standard => aho_corasick::MatchKind::Standard,
+            MatchKind::LeftmostFirst => aho_corasick::MatchKind::LeftmostFirst,
+            MatchKind::LeftmostLongest => aho_corasick::MatchKind::LeftmostLongest,
+        }
+    }
+}
+
+/// An expression replaced by the expressions feature, e.g. 'what' becomes 'nani'.
+///
+/// By default, expressions only match whole words, e.g. 'love' does not match 'glove'.
+///
+/// Example:
+/// ```
+/// use uwu_rs::{Expression, Uwu};
+///
+/// let uwu = Uwu::builder()
+///     .expressions()
+///     .insert_expression(Expression::new("love", "luv").match_inflections())
+///     .build();
+/// assert_eq!(uwu.uwuify("loved, loving glove").unwrap(), "luved, luving glove");
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Expression {
+    /// The text to replace, e.g. 'what'.
+    pub pattern: String,
+    /// The text to replace the pattern with, e.g. 'nani'.
+    pub replacement: String,
+    /// Whether the pattern also matches the start of longer words, e.g. 'cute' in 'cuteness'.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub prefix: bool,
+    /// Whether the pattern also matches the end of longer words, e.g. 'love' in 'glove'.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub suffix: bool,
+    /// Whether the pattern also matches its inflected forms, which keep their ending in the
+    /// replacement, e.g. 'loves', 'loved' and 'loving' become 'luvs', 'luved' and 'luving'. Does not
+    /// apply to patterns that match the start of longer words.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub inflections: bool,
+}
+
+impl Expression {
+    /// Create a new expression that only matches whole words.
+    pub fn new<P: Into<String>, R: Into<String>>(pattern: P, replacement: R) -> Self {
+        Self {
+            pattern: pattern.into(),
+            replacement: replacement.into(),
+            prefix: false,
+            suffix: false,
+            inflections: false,
+        }
+    }
+
+    /// Makes the pattern also match the start of longer words, e.g. 'cute' in 'cuteness'.
+    pub fn match_prefix(mut self) -> Self {
+        self.prefix = true;
+        self
+    }
+
+    /// Makes the pattern also match the end of longer words, e.g. 'love' in 'glove'.
+    pub fn match_suffix(mut self) -> Self {
+        self.suffix = true;
+        self
+    }
+
+    /// Makes the pattern also match its inflected forms, e.g. 'loved' and 'loving'.
+    pub fn match_inflections(mut self) -> Self {
+        self.inflections = true;
+        self
+    }
+}
+
+/// A conflict between the patterns of two expressions in a [`Dictionary`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Conflict {
+    /// The pattern affected by the conflict.
+    pub pattern: String,
+    /// The pattern that takes precedence over `pattern`.
+    pub other: String,
+    /// How the patterns conflict.
+    pub kind: ConflictKind,
+}
+
+/// Specifies how the patterns of two expressions conflict.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ConflictKind {
+    /// The pattern can never be replaced, because the other pattern always matches first, e.g. 'hiya'
+    /// with 'hi' and [`MatchKind::Standard`]. Engines refuse to build with such a dictionary.
+    Shadowed,
+    /// The patterns can match overlapping text, in which case only one of them is replaced, e.g.
+    /// 'hi' and 'in' in 'hint'.
+    Overlap,
+}
+
+/// The expressions replaced by the expressions feature.
+///
+/// Patterns are matched ignoring their case, and the replacements follow the case of the input
+/// when the lowercase feature is disabled. The default dictionary contains the classic
+/// expressions, e.g. 'what' becomes 'nani'.
+///
+/// Example:
+/// ```
+/// use uwu_rs::{Dictionary, Uwu};
+///
+/// let mut dictionary = Dictionary::empty();
+/// dictionary.insert("hello", "henwo");
+/// dictionary.insert("no", "nu");
+///
+/// let uwu = Uwu::builder().expressions().dictionary(dictionary).build();
+/// assert_eq!(uwu.uwuify("hello? no").unwrap(), "henwo? nu");
+/// ```
+///
+/// Dictionaries can also be loaded from TOML or JSON files, with the `toml` and `json` features:
+/// ```toml
+/// match_kind = "leftmost-longest"
+///
+/// [[expressions]]
+/// pattern = "hello"
+/// replacement = "henwo"
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "DictionaryFile", into = "DictionaryFile")
+)]
+pub struct Dictionary {
+    expressions: Vec<Expression>,
+    match_kind: MatchKind,
+}
+
+impl Default for Dictionary {
+    fn default() -> Self {
+        let mut dictionary = Self::empty();
+        for (pattern, replacement) in dict::EXPRESSIONS.iter().zip(dict::EXPRESSIONS_REPLACE) {
+            let mut expression = Expression::new(*pattern, replacement);
+            // Patterns starting with a space, e.g. ' n', match the start of words
+            expression.prefix = pattern.starts_with(' ');
+            expression.inflections = dict::EXPRESSIONS_INFLECTED.contains(pattern);
+            dictionary.insert_expression(expression);
+        }
+        dictionary
+    }
+}
+
+impl Dictionary {
+    /// Create a new dictionary with the default expressions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new dictionary without any expressions.
+    pub fn empty() -> Self {
+        Self {
+            expressions: Vec::new(),
+            match_kind: MatchKind::default(),
+        }
+    }
+
+    /// Adds an expression that only matches whole words, or replaces the replacement of an existing
+    /// pattern. Returns the previous replacement, if any.
+    pub fn insert<P: AsRef<str>, R: Into<String>>(
+        &mut self,
+        pattern: P,
+        replacement: R,
+    ) -> Option<String> {
+        let pattern = pattern.as_ref().to_lowercase();
+        let replacement = replacement.into();
+        match self.position(&pattern) {
+            Some(idx) => Some(core::mem::replace(
+                &mut self.expressions[idx].replacement,
+                replacement,
+            )),
+            None => {
+                self.expressions.push(Expression::new(pattern, replacement));
+                None
+            }
+        }
+    }
+
+    /// Adds an expression, or replaces an existing one with the same pattern. Returns the previous
+    /// expression, if any.
+    pub fn insert_expression(&mut self, mut expression: Expression) -> Option<Expression> {
+        expression.pattern = expression.pattern.to_lowercase();
+        match self.position(&expression.pattern) {
+            Some(idx) => Some(core::mem::replace(&mut self.expressions[idx], expression)),
+            None => {
+                self.expressions.push(expression);
+                None
+            }
+        }
+    }
+
+    /// Removes the expression with the given pattern. Returns its replacement, if any.
+    pub fn remove<P: AsRef<str>>(&mut self, pattern: P) -> Option<String> {
+        let idx = self.position(&pattern.as_ref().to_lowercase())?;
+        Some(self.expressions.remove(idx).replacement)
+    }
+
+    /// Returns the replacement of the given pattern, if any.
+    pub fn get<P: AsRef<str>>(&self, pattern: P) -> Option<&str> {
+        let idx = self.position(&pattern.as_ref().to_lowercase())?;
+        Some(&self.expressions[idx].replacement)
+    }
+
+    /// Removes every expression.
+    pub fn clear(&mut self) {
+        self.expressions.clear();
+    }
+
+    /// Iterates over the expressions, in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = &Expression> {
+        self.expressions.iter()
+    }
+
+    /// The number of expressions.
+    pub fn len(&self) -> usize {
+        self.expressions.len()
+    }
+
+    /// Whether there are no expressions.
+    pub fn is_empty(&self) -> bool {
+        self.expressions.is_empty()
+    }
+
+    /// The semantics used when multiple patterns match overlapping text.
+    pub fn match_kind(&self) -> MatchKind {
+        self.match_kind
+    }
+
+    /// Sets the semantics used when multiple patterns match overlapping text.
+    pub fn set_match_kind(&mut self, match_kind: MatchKind) {
+        self.match_kind = match_kind;
+    }
+
+    /// Finds the patterns that match overlapping text, with the current match semantics.
+    ///
+    /// Example:
+    /// ```
+    /// use uwu_rs::{ConflictKind, Dictionary, Expression};
+    ///
+    /// let mut dictionary = Dictionary::empty();
+    /// dictionary.insert_expression(Expression::new("hi", "hai").match_prefix());
+    /// dictionary.insert("hiya", "hiya~");
+    ///
+    /// let conflicts = dictionary.conflicts();
+    /// assert_eq!(conflicts[0].pattern, "hiya");
+    /// assert_eq!(conflicts[0].kind, ConflictKind::Shadowed);
+    /// ```
+    pub fn conflicts(&self) -> Vec<Conflict> {
+        let forms = self.forms();
+        let mut conflicts: Vec<Conflict> = Vec::new();
+        for (idx, form) in forms.iter().enumerate() {
+            let pattern = form.pattern.as_slice();
+            for (other_idx, other_form) in forms.iter().enumerate() {
+                let other = other_form.pattern.as_slice();
+                if form.expression == other_form.expression
+                    || pattern.is_empty()
+                    || other.is_empty()
+                {
+                    continue;
+                }
+
+                let shadowed = (pattern == other && other_idx < idx)
+                    || match self.match_kind {
+                        // The other pattern ends first somewhere inside this one
+                        MatchKind::Standard => contains(&pattern[..pattern.len() - 1], other),
+                        // The other pattern starts at the same position and was added first
+                        MatchKind::LeftmostFirst => other_idx < idx && pattern.starts_with(other),
+                        MatchKind::LeftmostLongest => false,
+                    };
+                // A boundary at the end of a word and one at the start of a word never overlap
+                let overlaps = contains(pattern, other)
+                    || (1..pattern.len().min(other.len()))
+                        .any(|len| pattern.ends_with(&other[..len]) && other[..len] != [BOUNDARY]);
+
+                // Inflected forms are only shadowed partially
+                let kind = if shadowed && !form.inflected {
+                    ConflictKind::Shadowed
+                } else if shadowed || overlaps {
+                    ConflictKind::Overlap
+                } else {
+                    continue;
+                };
+                let conflict = Conflict {
+                    pattern: self.expressions[form.expression].pattern.clone(),
+                    other: self.expressions[other_form.expression].pattern.clone(),
+                    kind,
+                };
+                match conflicts.iter_mut().find(|existing| {
+                    existing.pattern == conflict.pattern && existing.other == conflict.other
+                }) {
+                    Some(existing) if kind == ConflictKind::Shadowed => existing.kind = kind,
+                    Some(_) => {}
+                    None => conflicts.push(conflict),
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// Checks that every expression can be replaced, i.e. no pattern is empty or shadowed by
+    /// another one.
+    pub fn validate(&self) -> Result<(), UwuError> {
+        if self
+            .expressions
+            .iter()
+            .any(|expression| expression.pattern.is_empty() || expression.pattern.contains('\0'))
+        {
+            return Err(UwuError::InvalidDictionary(
+                "expression patterns must not be empty or contain NUL characters".to_string(),
+            ));
+        }
+        match self
+            .conflicts()
+            .into_iter()
+            .find(|conflict| conflict.kind == ConflictKind::Shadowed)
+        {
+            Some(conflict) => Err(UwuError::ExpressionConflict(
+                conflict.pattern,
+                conflict.other,
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Adds every expression of the other dictionary, replacing the existing ones with the same
+    /// pattern.
+    pub fn extend(&mut self, other: Dictionary) {
+        for expression in other.expressions {
+            self.insert_expression(expression);
+        }
+    }
+
+    /// Parses a dictionary from a TOML document.
+    #[cfg(feature = "toml")]
+    pub fn from_toml(input: &str) -> Result<Self, UwuError> {
+        let file: DictionaryFile =
+            toml::from_str(input).map_err(|err| UwuError::InvalidDictionary(err.to_string()))?;
+        Ok(file.into())
+    }
+
+    /// Parses a dictionary from a JSON document.
+    #[cfg(feature = "json")]
+    pub fn from_json(input: &str) -> Result<Self, UwuError> {
+        let file: DictionaryFile = serde_json::from_str(input)
+            .map_err(|err| UwuError::InvalidDictionary(err.to_string()))?;
+        Ok(file.into())
+    }
+
+    /// Loads a dictionary from a TOML or JSON file, depending on its extension.
+    #[cfg(feature = "std")]
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, UwuError> {
+        let path = path.as_ref();
+        let extension = path.extension().and_then(|extension| extension.to_str());
+        match extension {
+            #[cfg(feature = "toml")]
+            Some("toml") => Self::from_toml(&std::fs::read_to_string(path)?),
+            #[cfg(feature = "json")]
+            Some("json") => Self::from_json(&std::fs::read_to_string(path)?),
+            _ => Err(UwuError::InvalidDictionary(format!(
+                "unsupported dictionary file: {}",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Compiles the expressions into the patterns searched in the input, where the start and end of
+    /// words are marked with [`BOUNDARY`]. The patterns of every expression come first, followed by
+    /// their inflected forms.
+    ///
+    /// Legacy patterns starting with a space, e.g. ' q', also match after the other characters words
+    /// stutter after, i.e. whitespace and opening brackets and quotes, e.g. '(q' or '“q'.
+    pub(crate) fn forms(&self) -> Vec<Form> {
+        let mut forms = Vec::new();
+        for (idx, expression) in self.expressions.iter().enumerate() {
+            forms.push(Form::new(
+                idx,
+                expression,
+                &expression.pattern,
+                expression.replacement.clone(),
+                false,
+            ));
+
+            let legacy = expression
+                .pattern
+                .strip_prefix(' ')
+                .zip(expression.replacement.strip_prefix(' '));
+            if let Some((pattern, replacement)) = legacy {
+                for boundary in STUTTER_BOUNDARIES.into_iter().filter(|char| *char != ' ') {
+                    let mut form = Form::new(
+                        idx,
+                        expression,
+                        &format!("{boundary}{pattern}"),
+                        format!("{boundary}{replacement}"),
+                        false,
+                    );
+                    // Non-ASCII characters are word bytes, but like a space they may follow a
+                    // word, e.g. '’\u{3000}q'
+                    if form.pattern[0] == BOUNDARY {
+                        form.pattern.remove(0);
+                    }
+                    forms.push(form);
+                }
+            }
+        }
+        for (idx, expression) in self.expressions.iter().enumerate() {
+            if !expression.inflections || expression.prefix {
+                continue;
+            }
+            let mut inflected: Vec<String> = Vec::new();
+            for ending in dict::INFLECTIONS {
+                let (pattern, replacement) = (
+                    inflect(&expression.pattern, ending),
+                    inflect(&expression.replacement, ending),
+                );
+                if !inflected.contains(&pattern) {
+                    forms.push(Form::new(idx, expression, &pattern, replacement, true));
+                    inflected.push(pattern);
+                }
+            }
+        }
+        forms
+    }
+
+    fn position(&self, pattern: &str) -> Option<usize> {
+        self.expressions
+            .iter()
+            .position(|expression| expression.pattern == pattern)
+    }
+}
+
+impl<'a> IntoIterator for &'a Dictionary {
+    type Item = &'a Expression;
+    type IntoIter = core::slice::Iter<'a, Expression>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.expressions.iter()
+    }
+}
+
+/// The format of dictionary files.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct DictionaryFile {
+    #[serde(default)]
+    match_kind: MatchKind,
+    #[serde(default)]
+    expressions: Vec<Expression>,
+}
+
+#[cfg(feature = "serde")]
+impl From<Dictionary> for DictionaryFile {
+    fn from(dictionary: Dictionary) -> Self {
+        Self {
+            match_kind: dictionary.match_kind,
+            expressions: dictionary.expressions,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<DictionaryFile> for Dictionary {
+    fn from(file: DictionaryFile) -> Self {
+        let mut dictionary = Self::empty();
+        dictionary.match_kind = file.match_kind;
+        for expression in file.expressions {
+            dictionary.insert_expression(expression);
+        }
+        dictionary
+    }
+}
+
+/// The byte that marks the start or end of a word in the patterns searched in the input.
+pub(crate) const BOUNDARY: u8 = 0;
+
+/// Whether the byte is part of a word, i.e. an ASCII letter or digit, or part of a non-ASCII
+/// character.
+#[inline(always)]
+pub(crate) fn is_word_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || !byte.is_ascii()
+}
+
+/// A pattern searched in the input, compiled from an expression.
+#[derive(Clone, Debug)]
+pub(crate) struct Form {
+    /// The index of the expression in the dictionary.
+    pub(crate) expression: usize,
+    /// The pattern, with boundaries at the start and end of words.
+    pub(crate) pattern: Vec<u8>,
+    /// The number of input bytes matched by the pattern, i.e. without boundaries.
+    pub(crate) len: usize,
+    pub(crate) replacement: String,
+    pub(crate) inflected: bool,
+}
+
+impl Form {
+    fn new(
+        idx: usize,
+        expression: &Expression,
+        text: &str,
+        replacement: String,
+        inflected: bool,
+    ) -> Self {
+        let text = text.as_bytes();
+        let mut pattern = Vec::with_capacity(text.len() + 2);
+        for (pos, byte) in text.iter().enumerate() {
+            let boundary = match pos {
+                0 => !expression.suffix && is_word_byte(*byte),
+                _ => is_word_byte(text[pos - 1]) != is_word_byte(*byte),
+            };
+            if boundary {
+                pattern.push(BOUNDARY);
+            }
+            pattern.push(*byte);
+        }
+        if !expression.prefix && text.last().is_some_and(|byte| is_word_byte(*byte)) {
+            pattern.push(BOUNDARY);
+        }
+
+        Self {
+            expression: idx,
+            pattern,
+            len: text.len(),
+            replacement,
+            inflected,
+        }
+    }
+}
+
+/// Adds an inflection ending to a word, dropping its final 'e' when needed, e.g. 'love' and 'ing'
+/// become 'loving'.
+fn inflect(word: &str, ending: &str) -> String {
+    match word.strip_suffix('e') {
+        Some(stem) if ending.starts_with(['e', 'i']) => format!("{stem}{ending}"),
+        _ => format!("{word}{ending}"),
+    }
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Uwu;
+
+    #[test]
+    fn assert_dictionary() {
+        let mut dictionary = Dictionary::new();
+        assert_eq!(dictionary.len(), 8);
+        assert_eq!(dictionary.get("what"), Some("nani"));
+
+        assert_eq!(dictionary.insert("Hello", "henwo"), None);
+        assert_eq!(dictionary.insert("what", "nyani"), Some("nani".to_string()));
+        assert_eq!(dictionary.get("hello"), Some("henwo"));
+        assert_eq!(dictionary.get("what"), Some("nyani"));
+        assert_eq!(dictionary.remove("LOVE"), Some("luv".to_string()));
+        assert_eq!(dictionary.remove("love"), None);
+        assert_eq!(dictionary.len(), 8);
+
+        dictionary.clear();
+        assert!(dictionary.is_empty());
+    }
+
+    #[test]
+    fn assert_conflicts() {
+        assert!(Dictionary::new().validate().is_ok());
+
+        // Whole words never conflict with each other
+        let mut dictionary = Dictionary::empty();
+        dictionary.insert("hi", "hai");
+        dictionary.insert("hiya", "hiya~");
+        dictionary.insert("yay", "yay~");
+        assert!(dictionary.conflicts().is_empty());
+
+        let mut dictionary = Dictionary::empty();
+        dictionary.insert_expression(Expression::new("hi", "hai").match_prefix());
+        dictionary.insert_expression(Expression::new("hiya", "hiya~").match_prefix());
+        dictionary.insert_expression(Expression::new("yay", "yay~").match_suffix());
+
+        let conflict = |pattern: &str, other: &str, kind| Conflict {
+            pattern: pattern.to_string(),
+            other: other.to_string(),
+            kind,
+        };
+        assert_eq!(
+            dictionary.conflicts(),
+            [
+                conflict("hiya", "hi", ConflictKind::Shadowed),
+                conflict("hiya", "yay", ConflictKind::Overlap),
+            ]
+        );
+        assert!(matches!(
+            dictionary.validate(),
+            Err(UwuError::ExpressionConflict(..))
+        ));
+
+        dictionary.set_match_kind(MatchKind::LeftmostFirst);
+        assert_eq!(dictionary.conflicts()[0].kind, ConflictKind::Shadowed);
+        dictionary.set_match_kind(MatchKind::LeftmostLongest);
+        assert_eq!(dictionary.conflicts()[0].kind, ConflictKind::Overlap);
+        assert!(dictionary.validate().is_ok());
+
+        dictionary.insert("", "nothing");
+        assert!(matches!(
+            dictionary.validate(),
+            Err(UwuError::InvalidDictionary(..))
+        ));
+    }
+
+    //noinspection SpellCheckingInspection
+    #[test]
+    fn assert_custom_expressions() {
+        fn convert(match_kind: MatchKind, input: &str) -> String {
+            Uwu::builder()
+                .expressions()
+                .add_expression("hi", "hai")
+                .add_expression("hello", "henwo")
+                .insert_expression(Expression::new("hell", "heck").match_prefix())
+                .remove_expression("what")
+                .expressions_match_kind(match_kind)
+                .build()
+                .uwuify(input)
+                .unwrap()
+        }
+
+        assert!(Uwu::builder()
+            .add_expression("hello", "henwo")
+            .insert_expression(Expression::new("hell", "heck").match_prefix())
+            .build()
+            .compile()
+            .is_err());
+
+        assert_eq!(
+            convert(MatchKind::LeftmostFirst, "hi, hello hellish what this"),
+            "hai, henwo heckish what this"
+        );
+        assert_eq!(
+            convert(MatchKind::LeftmostLongest, "hi, hello hellish what this"),
+            "hai, henwo heckish what this"
+        );
+        assert_eq!(
+            convert(MatchKind::LeftmostLongest, "Hi, HELLO small cute hello"),
+            "Hai, HENWO smol kawaii~ henwo"
+        );
+    }
+
+    //noinspection SpellCheckingInspection
+    #[test]
+    fn assert_whole_words() {
+        fn convert(input: &str) -> String {
+            Uwu::builder().expressions().build().uwuify(input).unwrap()
+        }
+
+        assert_eq!(
+            convert("glove whatever cuteness"),
+            "glove whatever cuteness"
+        );
+        assert_eq!(convert("love, what? (cute)"), "luv, nani? (kawaii~)");
+        assert_eq!(convert("Loved LOVING lover"), "Luved LUVING luver");
+        assert_eq!(convert("not nine, n"), "not nine, n");
+        // Legacy patterns starting with a space match after whitespace and opening brackets
+        assert_eq!(
+            convert("quick (quiet)\nquest\t[Quiz] \"quote\" equal"),
+            "qwuick (qwuiet)\nqwuest\t[Qwuiz] \"qwuote\" equal"
+        );
+        assert_eq!(
+            convert("“quote” ¿quién? «quoi» ‘quay’\u{3000}quip\u{a0}quit ¡qué!"),
+            "“qwuote” ¿qwuién? «qwuoi» ‘qwuay’\u{3000}qwuip\u{a0}qwuit ¡qwué!"
+        );
+        assert_eq!(convert("»quoi ’quay"), "»quoi ’quay");
+        assert_eq!(convert("héllo small"), "héllo smol");
+
+        let uwu = Uwu::builder()
+            .expressions()
+            .clear_expressions()
+            .insert_expression(Expression::new("meow", "nya").match_suffix())
+            .insert_expression(Expression::new("cat", "neko").match_prefix())
+            .build();
+        assert_eq!(
+            uwu.uwuify("meow catnip: cat, meowing, somemeow").unwrap(),
+            "nya nekonip: neko, meowing, somenya"
+        );
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn assert_from_toml() {
+        let dictionary = Dictionary::from_toml(
+            r#"
+            match_kind = "leftmost-longest"
+
+            [[expressions]]
+            pattern = "hi"
+            replacement = "hai"
+
+            [[expressions]]
+            pattern = "hiya"
+            replacement = "hiya~"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(dictionary.match_kind(), MatchKind::LeftmostLongest);
+        assert_eq!(dictionary.get("hiya"), Some("hiya~"));
+
+        assert!(Dictionary::from_toml("expressions = 1").is_err());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn assert_from_json() {
+        let dictionary =
+            Dictionary::from_json(r#"{"expressions": [{"pattern": "no", "replacement": "nu"}]}"#)
+                .unwrap();
+        assert_eq!(dictionary.match_kind(), MatchKind::Standard);
+        assert_eq!(dictionary.get("no"), Some("nu"));
+
+        assert!(Dictionary::from_json(r#"{"expressions": 1}"#).is_err());
+    }
+}
@@ -0,0 +1,339 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use crate::{dict, UwuError};
+
+/// An emoji added by the emojis feature, e.g. 'OwO '.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Emoji {
+    /// The text inserted after the punctuation, as is. It usually ends with a space, e.g. 'OwO '.
+    pub text: String,
+    /// How likely the emoji is to be picked, relative to the other emojis in the set. An emoji
+    /// with a weight of 2 is picked twice as often as an emoji with a weight of 1.
+    #[cfg_attr(feature = "serde", serde(default = "Emoji::default_weight"))]
+    pub weight: u32,
+}
+
+impl Emoji {
+    #[cfg(feature = "serde")]
+    fn default_weight() -> u32 {
+        1
+    }
+}
+
+/// The emojis added by the emojis feature, each with its own weight.
+///
+/// The default set contains the classic emojis, e.g. 'OwO ' or ':3 '.
+///
+/// Example:
+/// ```
+/// use uwu_rs::{EmojiSet, Uwu};
+///
+/// let mut emoji_set = EmojiSet::empty();
+/// emoji_set.insert("OwO ", 3);
+/// emoji_set.insert("(=^･ω･^=) ", 1);
+///
+/// let uwu = Uwu::builder().emojis(1).emoji_set(emoji_set).build();
+/// let uwuified = uwu.uwuify("Hello, world!").unwrap();
+/// ```
+///
+/// Emoji sets can also be loaded from TOML or JSON files, with the `toml` and `json` features:
+/// ```toml
+/// [[emojis]]
+/// text = "OwO "
+/// weight = 3
+///
+/// [[emojis]]
+/// text = "(=^･ω･^=) "
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "EmojiSetFile", into = "EmojiSetFile")
+)]
+pub struct EmojiSet {
+    emojis: Vec<Emoji>,
+}
+
+impl Default for EmojiSet {
+    fn default() -> Self {
+        let mut emoji_set = Self::empty();
+        for text in dict::EMOJIS {
+            // Duplicates in the list add up to a higher weight
+            let weight = emoji_set.get(text).unwrap_or(0) + 1;
+            emoji_set.insert(text, weight);
+        }
+        emoji_set
+    }
+}
+
+impl EmojiSet {
+    /// Create a new set with the default emojis.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new set without any emojis.
+    pub fn empty() -> Self {
+        Self { emojis: Vec::new() }
+    }
+
+    /// Adds an emoji, or replaces the weight of an existing one. Returns the previous weight, if
+    /// any.
+    pub fn insert<T: Into<String>>(&mut self, text: T, weight: u32) -> Option<u32> {
+        let text = text.into();
+        match self.position(&text) {
+            Some(idx) => Some(core::mem::replace(&mut self.emojis[idx].weight, weight)),
+            None => {
+                self.emojis.push(Emoji { text, weight });
+                None
+            }
+        }
+    }
+
+    /// Removes an emoji. Returns its weight, if any.
+    pub fn remove<T: AsRef<str>>(&mut self, text: T) -> Option<u32> {
+        let idx = self.position(text.as_ref())?;
+        Some(self.emojis.remove(idx).weight)
+    }
+
+    /// Returns the weight of an emoji, if any.
+    pub fn get<T: AsRef<str>>(&self, text: T) -> Option<u32> {
+        let idx = self.position(text.as_ref())?;
+        Some(self.emojis[idx].weight)
+    }
+
+    /// Removes every emoji.
+    pub fn clear(&mut self) {
+        self.emojis.clear();
+    }
+
+    /// Iterates over the emojis, in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = &Emoji> {
+        self.emojis.iter()
+    }
+
+    /// The number of emojis.
+    pub fn len(&self) -> usize {
+        self.emojis.len()
+    }
+
+    /// Whether there are no emojis.
+    pub fn is_empty(&self) -> bool {
+        self.emojis.is_empty()
+    }
+
+    /// The sum of the weights of every emoji.
+    pub fn total_weight(&self) -> u64 {
+        self.emojis.iter().map(|emoji| emoji.weight as u64).sum()
+    }
+
+    /// Checks that emojis can be picked from this set, i.e. at least one emoji has a weight
+    /// greater than 0.
+    pub fn validate(&self) -> Result<(), UwuError> {
+        match self.total_weight() {
+            0 => Err(UwuError::InvalidEmojiSet(
+                "at least one emoji must have a weight greater than 0".to_string(),
+            )),
+            total if usize::try_from(total).is_err() => Err(UwuError::InvalidEmojiSet(
+                "the total weight of the emojis is too large".to_string(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Adds every emoji of the other set, replacing the weights of the existing ones.
+    pub fn extend(&mut self, other: EmojiSet) {
+        for emoji in other.emojis {
+            self.insert(emoji.text, emoji.weight);
+        }
+    }
+
+    /// Parses an emoji set from a TOML document.
+    #[cfg(feature = "toml")]
+    pub fn from_toml(input: &str) -> Result<Self, UwuError> {
+        let file: EmojiSetFile =
+            toml::from_str(input).map_err(|err| UwuError::InvalidEmojiSet(err.to_string()))?;
+        Ok(file.into())
+    }
+
+    /// Parses an emoji set from a JSON document.
+    #[cfg(feature = "json")]
+    pub fn from_json(input: &str) -> Result<Self, UwuError> {
+        let file: EmojiSetFile = serde_json::from_str(input)
+            .map_err(|err| UwuError::InvalidEmojiSet(err.to_string()))?;
+        Ok(file.into())
+    }
+
+    /// Loads an emoji set from a TOML or JSON file, depending on its extension.
+    #[cfg(feature = "std")]
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, UwuError> {
+        let path = path.as_ref();
+        let extension = path.extension().and_then(|extension| extension.to_str());
+        match extension {
+            #[cfg(feature = "toml")]
+            Some("toml") => Self::from_toml(&std::fs::read_to_string(path)?),
+            #[cfg(feature = "json")]
+            Some("json") => Self::from_json(&std::fs::read_to_string(path)?),
+            _ => Err(UwuError::InvalidEmojiSet(format!(
+                "unsupported emoji set file: {}",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Picks a random emoji, following the weights. The set must not be empty.
+    pub(crate) fn choose(&self, rng: &mut fastrand::Rng) -> &str {
+        // Picking an index in the total weight keeps the same sequence as picking from a list
+        // where every emoji is repeated by its weight
+        let mut target = rng.usize(0..self.total_weight() as usize);
+        for emoji in &self.emojis {
+            if target < emoji.weight as usize {
+                return &emoji.text;
+            }
+            target -= emoji.weight as usize;
+        }
+        unreachable!("the target is always lower than the total weight")
+    }
+
+    fn position(&self, text: &str) -> Option<usize> {
+        self.emojis.iter().position(|emoji| emoji.text == text)
+    }
+}
+
+impl<'a> IntoIterator for &'a EmojiSet {
+    type Item = &'a Emoji;
+    type IntoIter = core::slice::Iter<'a, Emoji>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.emojis.iter()
+    }
+}
+
+/// The format of emoji set files.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct EmojiSetFile {
+    #[serde(default)]
+    emojis: Vec<Emoji>,
+}
+
+#[cfg(feature = "serde")]
+impl From<EmojiSet> for EmojiSetFile {
+    fn from(emoji_set: EmojiSet) -> Self {
+        Self {
+            emojis: emoji_set.emojis,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<EmojiSetFile> for EmojiSet {
+    fn from(file: EmojiSetFile) -> Self {
+        let mut emoji_set = Self::empty();
+        for emoji in file.emojis {
+            emoji_set.insert(emoji.text, emoji.weight);
+        }
+        emoji_set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Uwu, DEFAULT_SEED};
+
+    #[test]
+    fn assert_emoji_set() {
+        let mut emoji_set = EmojiSet::new();
+        assert_eq!(emoji_set.len(), 31);
+        assert_eq!(emoji_set.total_weight(), 32);
+        assert_eq!(emoji_set.get(":3 "), Some(2));
+
+        assert_eq!(emoji_set.insert("OwO ", 5), Some(1));
+        assert_eq!(emoji_set.remove(":3 "), Some(2));
+        assert_eq!(emoji_set.remove(":3 "), None);
+        assert_eq!(emoji_set.total_weight(), 34);
+
+        emoji_set.clear();
+        assert!(emoji_set.validate().is_err());
+        emoji_set.insert("OwO ", 0);
+        assert!(emoji_set.validate().is_err());
+    }
+
+    #[test]
+    fn assert_weighted_choice() {
+        // The default set picks the same emojis as picking from the list with duplicates
+        let emoji_set = EmojiSet::default();
+        let mut rng = fastrand::Rng::with_seed(DEFAULT_SEED);
+        let mut list_rng = rng.clone();
+        for _ in 0..1000 {
+            assert_eq!(
+                emoji_set.choose(&mut rng),
+                list_rng.choice(dict::EMOJIS).unwrap()
+            );
+        }
+
+        let mut emoji_set = EmojiSet::empty();
+        emoji_set.insert("OwO ", 3);
+        emoji_set.insert("UwU ", 1);
+        emoji_set.insert("never ", 0);
+        let owo = (0..1000)
+            .filter(|_| emoji_set.choose(&mut rng) == "OwO ")
+            .count();
+        assert!((700..800).contains(&owo), "{owo}");
+    }
+
+    #[test]
+    fn assert_custom_emojis() {
+        let uwu = Uwu::builder()
+            .emojis(1)
+            .clear_emojis()
+            .add_emoji("(=^･ω･^=) ", 1)
+            .build();
+        assert_eq!(
+            uwu.uwuify("hello, world!").unwrap(),
+            "hello, (=^･ω･^=) world! (=^･ω･^=)"
+        );
+
+        assert!(Uwu::builder()
+            .emojis(1)
+            .clear_emojis()
+            .build_engine()
+            .is_err());
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn assert_from_toml() {
+        let emoji_set = EmojiSet::from_toml(
+            r#"
+            [[emojis]]
+            text = "OwO "
+            weight = 3
+
+            [[emojis]]
+            text = "UwU "
+            "#,
+        )
+        .unwrap();
+        assert_eq!(emoji_set.get("OwO "), Some(3));
+        assert_eq!(emoji_set.get("UwU "), Some(1));
+
+        assert!(EmojiSet::from_toml("emojis = 1").is_err());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn assert_from_json() {
+        let emoji_set =
+            EmojiSet::from_json(r#"{"emojis": [{"text": ":3 ", "weight": 2}]}"#).unwrap();
+        assert_eq!(emoji_set.get(":3 "), Some(2));
+
+        assert!(EmojiSet::from_json(r#"{"emojis": 1}"#).is_err());
+    }
+}
@@ -0,0 +1,368 @@
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+#[cfg(feature = "std")]
+use std::io::{Read, Write};
+
+use aho_corasick::automaton::{Automaton, StateID};
+use aho_corasick::dfa::DFA;
+use aho_corasick::{AhoCorasick, Anchored};
+
+use crate::dictionary::{is_word_byte, Form, BOUNDARY};
+use crate::pipeline::{self, Markup, Pipeline};
+use crate::protection;
+use crate::seed::FeatureRngs;
+use crate::stutter::StutterRules;
+use crate::{ProtectedSpan, Shared, Uwu, UwuError};
+#[cfg(feature = "std")]
+use crate::{UwuReader, UwuWriter};
+
+/// A compiled `Uwu` instance, ready to run the uwu algorithm many times.
+///
+/// Building the string matchers used by the expressions and emojis features is expensive, so the
+/// engine builds them once and reuses them on every call. The input is then uwuified in a single
+/// pass, running all features at once. It is cheap to clone, and can be shared
+/// between threads, e.g. with an `Arc`, on targets with atomic pointers.
+///
+/// Example:
+/// ```
+/// let engine = uwu_rs::Uwu::builder()
+///     .lowercase()
+///     .expressions()
+///     .w_replace()
+///     .stutter(4)
+///     .emojis(1)
+///     .build_engine()
+///     .unwrap();
+/// let uwuified = engine.uwuify("Hello world!");
+/// ```
+#[derive(Clone, Debug)]
+pub struct UwuEngine {
+    pub(crate) uwu: Uwu,
+    pub(crate) expressions: Shared<DFA>,
+    pub(crate) expressions_start: StateID,
+    /// The patterns searched by the expressions automaton, by pattern ID.
+    pub(crate) expressions_forms: Shared<[Form]>,
+    pub(crate) punctuation: Shared<DFA>,
+    pub(crate) punctuation_start: StateID,
+    /// Searches the words passed through verbatim, if any.
+    pub(crate) protected_words: Option<Shared<AhoCorasick>>,
+    /// The lowercase words left alone by the nya feature.
+    pub(crate) nya_exceptions: Shared<BTreeSet<String>>,
+    /// Whether each byte may start a nya exception, so the nya step holds the words it starts.
+    pub(crate) nya_starts: [bool; 256],
+    /// Picks the words that stutter and how.
+    pub(crate) stutter: Shared<StutterRules>,
+    /// Whether each input byte leaves the pipeline idle, and so can be copied without running every
+    /// feature, e.g. most letters. Indexed by whether the previous byte was part of a word, since
+    /// the start and end of words can be part of an expression.
+    pub(crate) passthrough: [[bool; 256]; 2],
+    /// Maps each input byte in the passthrough table to its output.
+    pub(crate) passthrough_map: [u8; 256],
+}
+
+impl UwuEngine {
+    /// Compiles the `Uwu` instance into a new engine.
+    pub fn new(uwu: Uwu) -> Result<Self, UwuError> {
+        uwu.validate()?;
+        let expressions_forms = uwu.dictionary.forms();
+        let expressions = DFA::builder()
+            .ascii_case_insensitive(!uwu.lowercase)
+            .match_kind(uwu.dictionary.match_kind().into())
+            .build(expressions_forms.iter().map(|form| &form.pattern))?;
+        let expressions_start = expressions.start_state(Anchored::No)?;
+        let boundary_idle = !uwu.expressions
+            || expressions.next_state(Anchored::No, expressions_start, BOUNDARY)
+                == expressions_start;
+        let punctuation = DFA::builder().build(
+            uwu.punctuation_set
+                .iter()
+                .map(|punctuation| &punctuation.text),
+        )?;
+        let punctuation_start = punctuation.start_state(Anchored::No)?;
+        let protected_words = if uwu.protected_words.is_empty() {
+            None
+        } else {
+            let matcher = AhoCorasick::builder()
+                .match_kind(aho_corasick::MatchKind::LeftmostLongest)
+                .build(&uwu.protected_words)?;
+            Some(Shared::new(matcher))
+        };
+
+        // A stutter boundary must go through the pipeline, which only needs the first byte of a
+        // multi-byte character to notice it
+        let mut stutter_boundaries = [false; 256];
+        for char in pipeline::STUTTER_BOUNDARIES {
+            stutter_boundaries[char.encode_utf8(&mut [0; 4]).as_bytes()[0] as usize] = true;
+        }
+        let nya_exceptions = uwu
+            .nya_exceptions
+            .iter()
+            .map(|word| word.to_lowercase())
+            .collect::<BTreeSet<_>>();
+        let mut nya_starts = [false; 256];
+        for word in &nya_exceptions {
+            match word.bytes().next() {
+                Some(byte) if byte.is_ascii() => {
+                    nya_starts[byte.to_ascii_lowercase() as usize] = true;
+                    nya_starts[byte.to_ascii_uppercase() as usize] = true;
+                }
+                // The case of other characters may change their first byte, e.g. 'Σ' and 'σ'
+                Some(_) => nya_starts[0x80..].fill(true),
+                None => {}
+            }
+        }
+        // Runs of passthrough bytes end at the start and end of words when expressions or nya
+        // exceptions look for them
+        let boundary_idle = boundary_idle && !(uwu.nya && nya_starts.contains(&true));
+        let mut passthrough = [[false; 256]; 2];
+        let mut passthrough_map = [0; 256];
+        for byte in 0..=u8::MAX {
+            let lowercase = if uwu.lowercase {
+                byte.to_ascii_lowercase()
+            } else {
+                byte
+            };
+            let replaced = if uwu.w_replace {
+                pipeline::w_replace(lowercase)
+            } else {
+                lowercase
+            };
+            let idle = (!uwu.stutter || !stutter_boundaries[replaced as usize])
+                && (!uwu.expressions
+                    || expressions.next_state(Anchored::No, expressions_start, lowercase)
+                        == expressions_start)
+                && (!uwu.emojis
+                    || punctuation.next_state(Anchored::No, punctuation_start, replaced)
+                        == punctuation_start);
+            for after_word in [false, true] {
+                // The nya step holds every 'n', and the words that may be an exception
+                let nya_idle = !uwu.nya
+                    || !(matches!(lowercase, b'n' | b'N')
+                        || !after_word && nya_starts[lowercase as usize]);
+                passthrough[after_word as usize][byte as usize] =
+                    idle && nya_idle && (boundary_idle || is_word_byte(byte) == after_word);
+            }
+            passthrough_map[byte as usize] = replaced;
+        }
+
+        let stutter = Shared::new(StutterRules::new(&uwu));
+        Ok(Self {
+            uwu,
+            expressions: Shared::new(expressions),
+            expressions_start,
+            expressions_forms: expressions_forms.into(),
+            punctuation: Shared::new(punctuation),
+            punctuation_start,
+            protected_words,
+            nya_exceptions: Shared::new(nya_exceptions),
+            nya_starts,
+            stutter,
+            passthrough,
+            passthrough_map,
+        })
+    }
+
+    /// The `Uwu` instance this engine was compiled from.
+    pub fn uwu(&self) -> &Uwu {
+        &self.uwu
+    }
+
+    /// Converts the input text into an uwuified version.
+    ///
+    /// Example:
+    /// ```
+    /// let engine = uwu_rs::UwuEngine::new(uwu_rs::Uwu::new()).unwrap();
+    /// let uwuified = engine.uwuify("Hello world!");
+    /// ```
+    pub fn uwuify<S: AsRef<str>>(&self, input: S) -> Result<String, UwuError> {
+        let input = input.as_ref();
+        let rngs = FeatureRngs::new(self.uwu.seed.create_rng(input));
+        self.uwuify_bytes(input, rngs, Markup::Plain)
+    }
+
+    /// Converts the input text into an uwuified version, using a caller-owned random number
+    /// generator instead of the configured [`crate::Seed`]. The generator is advanced on every
+    /// call, so repeated calls produce different outputs.
+    pub fn uwuify_with_rng<S: AsRef<str>>(
+        &self,
+        input: S,
+        rng: &mut fastrand::Rng,
+    ) -> Result<String, UwuError> {
+        let rngs = FeatureRngs::fork(rng);
+        self.uwuify_bytes(input.as_ref(), rngs, Markup::Plain)
+    }
+
+    /// Converts the input text into an uwuified version, passing the given spans through verbatim,
+    /// e.g. usernames or quoted code. Fails if a span is out of bounds or does not start and end on
+    /// character boundaries.
+    ///
+    /// Example:
+    /// ```
+    /// use uwu_rs::{ProtectedSpan, Uwu};
+    ///
+    /// let engine = Uwu::builder().w_replace().build_engine().unwrap();
+    /// let uwuified = engine.uwuify_protecting("Hello Laura", &[ProtectedSpan::Bytes(6..11)]);
+    /// assert_eq!(uwuified.unwrap(), "Hewwo Laura");
+    /// ```
+    pub fn uwuify_protecting<S: AsRef<str>>(
+        &self,
+        input: S,
+        spans: &[ProtectedSpan],
+    ) -> Result<String, UwuError> {
+        let input = input.as_ref();
+        let protected = self.protected_ranges(input, spans)?;
+        let rngs = FeatureRngs::new(self.uwu.seed.create_rng(input));
+
+        let mut buf = Vec::with_capacity(input.len() + input.len() / 2);
+        let mut pipeline = Pipeline::new(self, rngs);
+        pipeline.push_protected(self, input, &protected, &mut buf);
+        pipeline.finish(self, &mut buf);
+        Ok(into_string(buf))
+    }
+
+    /// Converts the prose of a CommonMark document into an uwuified version, leaving its structure,
+    /// code, link targets, HTML and front matter untouched.
+    ///
+    /// Example:
+    /// ```
+    /// let engine = uwu_rs::Uwu::new().compile().unwrap();
+    /// let uwuified = engine.uwuify_markdown("Hello *world*! `let l = r;`").unwrap();
+    /// assert_eq!(uwuified, "hewwo *wowwd*! 😳😳😳 `let l = r;`");
+    /// ```
+    #[cfg(feature = "markdown")]
+    pub fn uwuify_markdown<S: AsRef<str>>(&self, input: S) -> Result<String, UwuError> {
+        let input = input.as_ref();
+        self.uwuify_ranges(input, crate::markdown::prose(input), Markup::Markdown)
+    }
+
+    /// Converts the visible text of an HTML document into an uwuified version, leaving its markup,
+    /// character references and the elements skipped by the options untouched.
+    ///
+    /// Example:
+    /// ```
+    /// let engine = uwu_rs::Uwu::new().compile().unwrap();
+    /// let options = uwu_rs::HtmlOptions::new();
+    /// let uwuified = engine.uwuify_html(r#"<p class="scroll">Hello world</p>"#, &options);
+    /// ```
+    #[cfg(feature = "html")]
+    pub fn uwuify_html<S: AsRef<str>>(
+        &self,
+        input: S,
+        options: &crate::HtmlOptions,
+    ) -> Result<String, UwuError> {
+        let input = input.as_ref();
+        self.uwuify_ranges(input, crate::html::prose(input, options), Markup::Html)
+    }
+
+    /// Reads text from the reader and writes the uwuified version to the writer, using a bounded
+    /// amount of memory. The output is identical to calling [`UwuEngine::uwuify`] with the whole
+    /// input, as long as the seed does not depend on the input (see [`crate::Seed::Input`]), except
+    /// in rare cases within runs of more than 64 KiB without whitespace.
+    ///
+    /// Returns the number of bytes written.
+    #[cfg(feature = "std")]
+    pub fn uwuify_stream<R: Read, W: Write>(&self, reader: R, writer: W) -> Result<u64, UwuError> {
+        let mut reader = self.reader(reader);
+        let mut writer = writer;
+        let written = std::io::copy(&mut reader, &mut writer)?;
+        writer.flush()?;
+        Ok(written)
+    }
+
+    /// Creates a new reader that uwuifies the text read from `inner`.
+    #[cfg(feature = "std")]
+    pub fn reader<R: Read>(&self, inner: R) -> UwuReader<R> {
+        UwuReader::new(self.clone(), inner)
+    }
+
+    /// Creates a new writer that uwuifies the text before writing it to `inner`.
+    #[cfg(feature = "std")]
+    pub fn writer<W: Write>(&self, inner: W) -> UwuWriter<W> {
+        UwuWriter::new(self.clone(), inner)
+    }
+
+    /// Uwuifies the given ranges of a document, copying everything else as is.
+    ///
+    /// Every range is uwuified on its own, with generators derived from a single one seeded by the
+    /// whole document, so expressions never match across markup.
+    #[cfg(any(feature = "markdown", feature = "html"))]
+    fn uwuify_ranges(
+        &self,
+        input: &str,
+        ranges: Vec<core::ops::Range<usize>>,
+        markup: Markup,
+    ) -> Result<String, UwuError> {
+        let mut rng = self.uwu.seed.create_rng(input);
+        let mut output = String::with_capacity(input.len() + input.len() / 2);
+        let mut copied = 0;
+        for range in ranges {
+            output.push_str(&input[copied..range.start]);
+            let rngs = FeatureRngs::fork(&mut rng);
+            output.push_str(&self.uwuify_bytes(&input[range.clone()], rngs, markup)?);
+            copied = range.end;
+        }
+        output.push_str(&input[copied..]);
+        Ok(output)
+    }
+
+    fn uwuify_bytes(
+        &self,
+        input: &str,
+        rngs: FeatureRngs,
+        markup: Markup,
+    ) -> Result<String, UwuError> {
+        let mut buf = Vec::with_capacity(input.len() + input.len() / 2);
+        let mut pipeline = Pipeline::new(self, rngs).markup(markup);
+        pipeline.push(self, input, &mut buf);
+        pipeline.finish(self, &mut buf);
+        Ok(into_string(buf))
+    }
+
+    /// Finds the ranges of the input that are passed through verbatim.
+    pub(crate) fn protected_ranges(
+        &self,
+        input: &str,
+        spans: &[ProtectedSpan],
+    ) -> Result<Vec<Range<usize>>, UwuError> {
+        protection::protected_ranges(
+            &self.uwu.protection,
+            self.protected_words.as_deref(),
+            input,
+            spans,
+        )
+    }
+}
+
+pub(crate) fn into_string(buf: Vec<u8>) -> String {
+    String::from_utf8(buf)
+        .unwrap_or_else(|err| String::from_utf8_lossy(err.as_bytes()).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[test]
+    fn assert_engine_shareable() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<UwuEngine>();
+
+        let engine = Arc::new(Uwu::new().compile().unwrap());
+        let input = "The quick brown fox jumps over the lazy dog. What a cute dog!";
+        let expected = Uwu::new().uwuify(input).unwrap();
+
+        let handles = (0..4)
+            .map(|_| {
+                let engine = Arc::clone(&engine);
+                std::thread::spawn(move || engine.uwuify(input).unwrap())
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), expected);
+        }
+    }
+}
@@ -0,0 +1,442 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// The elements whose content is raw text, i.e. it can not contain markup and ends at the first
+/// matching end tag.
+const RAW_TEXT: [&str; 4] = ["script", "style", "textarea", "title"];
+
+/// Specifies which parts of an HTML document are uwuified.
+///
+/// By default, only visible text nodes are uwuified, skipping the content of `script`, `style`,
+/// `pre` and `code` elements. Tags, comments and entities like '&amp;' are always left untouched.
+///
+/// Example:
+/// ```
+/// use uwu_rs::{HtmlOptions, Uwu};
+///
+/// let uwu = Uwu::builder().w_replace().build();
+/// let uwuified = uwu
+///     .uwuify_html(
+///         r#"<img class="really" alt="A red lorry"><code>let l = r;</code>"#,
+///         HtmlOptions::new().attribute("alt"),
+///     )
+///     .unwrap();
+/// assert_eq!(uwuified, r#"<img class="really" alt="A wed wowwy"><code>let l = r;</code>"#);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HtmlOptions {
+    attributes: Vec<String>,
+    skipped_elements: Vec<String>,
+}
+
+impl Default for HtmlOptions {
+    fn default() -> Self {
+        Self {
+            attributes: Vec::new(),
+            skipped_elements: ["script", "style", "pre", "code"]
+                .map(String::from)
+                .to_vec(),
+        }
+    }
+}
+
+impl HtmlOptions {
+    /// Create new options that only uwuify visible text nodes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also uwuifies the quoted values of the attribute, e.g. `alt` or `title`.
+    pub fn attribute<T: Into<String>>(&mut self, name: T) -> &mut Self {
+        let name = name.into().to_ascii_lowercase();
+        if !self.attributes.contains(&name) {
+            self.attributes.push(name);
+        }
+        self
+    }
+
+    /// Stops uwuifying the values of the attribute.
+    pub fn remove_attribute<T: AsRef<str>>(&mut self, name: T) -> &mut Self {
+        self.attributes
+            .retain(|attribute| !attribute.eq_ignore_ascii_case(name.as_ref()));
+        self
+    }
+
+    /// Skips the text inside the element, e.g. `kbd`.
+    pub fn skip_element<T: Into<String>>(&mut self, name: T) -> &mut Self {
+        let name = name.into().to_ascii_lowercase();
+        if !self.skipped_elements.contains(&name) {
+            self.skipped_elements.push(name);
+        }
+        self
+    }
+
+    /// Uwuifies the text inside the element, even if it was skipped by default, e.g. `code`.
+    pub fn unskip_element<T: AsRef<str>>(&mut self, name: T) -> &mut Self {
+        self.skipped_elements
+            .retain(|element| !element.eq_ignore_ascii_case(name.as_ref()));
+        self
+    }
+
+    fn is_attribute(&self, name: &str) -> bool {
+        self.attributes
+            .iter()
+            .any(|attribute| attribute.eq_ignore_ascii_case(name))
+    }
+
+    fn is_skipped(&self, name: &str) -> bool {
+        self.skipped_elements
+            .iter()
+            .any(|element| element.eq_ignore_ascii_case(name))
+    }
+}
+
+/// Escapes the characters of the text that have a meaning in HTML, both in text and in attribute
+/// values.
+pub(crate) fn escape(text: &str, out: &mut Vec<u8>) {
+    for byte in text.bytes() {
+        match byte {
+            b'&' => out.extend_from_slice(b"&amp;"),
+            b'<' => out.extend_from_slice(b"&lt;"),
+            b'>' => out.extend_from_slice(b"&gt;"),
+            b'"' => out.extend_from_slice(b"&quot;"),
+            b'\'' => out.extend_from_slice(b"&#39;"),
+            _ => out.push(byte),
+        }
+    }
+}
+
+/// Finds the ranges of the input with visible text, plus the values of the selected attributes.
+///
+/// Character references are never part of a range, so they are preserved as written.
+pub(crate) fn prose(input: &str, options: &HtmlOptions) -> Vec<Range<usize>> {
+    let bytes = input.as_bytes();
+    let mut ranges = Vec::new();
+    // How many skipped elements the current position is in
+    let mut skipped = 0usize;
+    let mut text_start = 0;
+    let mut idx = 0;
+    while idx < bytes.len() {
+        if bytes[idx] != b'<' {
+            idx += 1;
+            continue;
+        }
+        let Some(tag) = Tag::parse(input, idx) else {
+            // A '<' that does not start markup is text, e.g. 'a < b'
+            idx += 1;
+            continue;
+        };
+
+        if skipped == 0 {
+            text(input, text_start..idx, &mut ranges);
+        }
+        idx = tag.end;
+        text_start = idx;
+
+        let Some(name) = tag.name.clone().map(|name| &input[name]) else {
+            continue;
+        };
+        if tag.closing {
+            if options.is_skipped(name) {
+                skipped = skipped.saturating_sub(1);
+            }
+            continue;
+        }
+
+        if skipped == 0 {
+            for (attribute, value) in &tag.attributes {
+                if options.is_attribute(&input[attribute.clone()]) {
+                    text(input, value.clone(), &mut ranges);
+                }
+            }
+        }
+        let raw_text = RAW_TEXT
+            .iter()
+            .any(|element| element.eq_ignore_ascii_case(name));
+        if raw_text {
+            // The content runs until the end tag, and is either text or skipped as a whole
+            let end = find_end_tag(input, idx, name);
+            if skipped == 0 && !options.is_skipped(name) {
+                text(input, idx..end, &mut ranges);
+            }
+            idx = end;
+            text_start = idx;
+        } else if !tag.self_closing && options.is_skipped(name) {
+            skipped += 1;
+        }
+    }
+    if skipped == 0 {
+        text(input, text_start..bytes.len(), &mut ranges);
+    }
+    ranges
+}
+
+/// A piece of markup, i.e. a tag, a comment or a doctype.
+#[derive(Debug)]
+struct Tag {
+    /// The name of the element, for start and end tags.
+    name: Option<Range<usize>>,
+    closing: bool,
+    self_closing: bool,
+    /// The names and quoted values of the attributes, without the quotes.
+    attributes: Vec<(Range<usize>, Range<usize>)>,
+    /// The index right after the markup.
+    end: usize,
+}
+
+impl Tag {
+    /// Parses the markup that starts with the '<' at the given index, if any.
+    fn parse(input: &str, start: usize) -> Option<Self> {
+        let bytes = input.as_bytes();
+        let rest = &input[start..];
+        let markup = |end| Tag {
+            name: None,
+            closing: false,
+            self_closing: false,
+            attributes: Vec::new(),
+            end,
+        };
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            let end = comment
+                .find("-->")
+                .map_or(bytes.len(), |idx| start + 4 + idx + 3);
+            return Some(markup(end));
+        }
+        let closing = rest.starts_with("</");
+        let name_start = start + 1 + closing as usize;
+        match bytes.get(name_start) {
+            Some(byte) if byte.is_ascii_alphabetic() => {}
+            // Doctypes, processing instructions and malformed end tags run until the next '>'
+            Some(b'!' | b'?') if !closing => return Some(markup(find_close(bytes, start))),
+            Some(_) if closing => return Some(markup(find_close(bytes, start))),
+            _ => return None,
+        }
+
+        let mut idx = name_start;
+        while idx < bytes.len() && !is_tag_delimiter(bytes[idx]) {
+            idx += 1;
+        }
+        let mut tag = Tag {
+            name: Some(name_start..idx),
+            closing,
+            self_closing: false,
+            attributes: Vec::new(),
+            end: bytes.len(),
+        };
+
+        while idx < bytes.len() {
+            match bytes[idx] {
+                b'>' => {
+                    tag.self_closing = bytes[idx - 1] == b'/';
+                    tag.end = idx + 1;
+                    break;
+                }
+                byte if byte.is_ascii_whitespace() || byte == b'/' => idx += 1,
+                _ => {
+                    let name_start = idx;
+                    while idx < bytes.len() && !is_tag_delimiter(bytes[idx]) && bytes[idx] != b'=' {
+                        idx += 1;
+                    }
+                    let name = name_start..idx.max(name_start + 1);
+                    idx = name.end;
+                    if let Some((value, end)) = parse_value(bytes, idx) {
+                        if let Some(value) = value {
+                            tag.attributes.push((name, value));
+                        }
+                        idx = end;
+                    }
+                }
+            }
+        }
+        Some(tag)
+    }
+}
+
+/// Parses the value of an attribute whose name ends at the given index. Returns the range of the
+/// value if it is quoted, and the index after it.
+fn parse_value(bytes: &[u8], mut idx: usize) -> Option<(Option<Range<usize>>, usize)> {
+    while idx < bytes.len() && bytes[idx].is_ascii_whitespace() {
+        idx += 1;
+    }
+    if bytes.get(idx) != Some(&b'=') {
+        return None;
+    }
+    idx += 1;
+    while idx < bytes.len() && bytes[idx].is_ascii_whitespace() {
+        idx += 1;
+    }
+    match bytes.get(idx) {
+        Some(quote @ (b'"' | b'\'')) => {
+            let start = idx + 1;
+            let end = bytes[start..]
+                .iter()
+                .position(|byte| byte == quote)
+                .map_or(bytes.len(), |len| start + len);
+            Some((Some(start..end), (end + 1).min(bytes.len())))
+        }
+        // Unquoted values can not contain spaces, so they are never uwuified
+        _ => {
+            while idx < bytes.len() && !bytes[idx].is_ascii_whitespace() && bytes[idx] != b'>' {
+                idx += 1;
+            }
+            Some((None, idx))
+        }
+    }
+}
+
+/// Adds the text in the range, without the character references.
+fn text(input: &str, range: Range<usize>, ranges: &mut Vec<Range<usize>>) {
+    let bytes = input.as_bytes();
+    let mut start = range.start;
+    let mut idx = range.start;
+    let mut push = |range: Range<usize>| {
+        if input[range.clone()].contains(|char: char| !char.is_whitespace()) {
+            ranges.push(range);
+        }
+    };
+    while idx < range.end {
+        if bytes[idx] == b'&' {
+            if let Some(len) = reference_len(&bytes[idx..range.end]) {
+                push(start..idx);
+                idx += len;
+                start = idx;
+                continue;
+            }
+        }
+        idx += 1;
+    }
+    push(start..range.end);
+}
+
+/// The length of the character reference at the start of the bytes, e.g. `&amp;`, `&#123;` or
+/// `&#x1F;`, if it is well-formed.
+fn reference_len(bytes: &[u8]) -> Option<usize> {
+    let (start, is_digit): (usize, fn(&u8) -> bool) = match bytes.get(1..3) {
+        Some([b'#', b'x' | b'X']) => (3, u8::is_ascii_hexdigit),
+        _ if bytes.get(1) == Some(&b'#') => (2, u8::is_ascii_digit),
+        _ => (1, u8::is_ascii_alphanumeric),
+    };
+    let len = bytes[start..]
+        .iter()
+        .take_while(|byte| is_digit(byte))
+        .count();
+    (len > 0 && bytes.get(start + len) == Some(&b';')).then_some(start + len + 1)
+}
+
+/// Finds the start of the end tag of the raw text element, or the end of the input.
+fn find_end_tag(input: &str, start: usize, name: &str) -> usize {
+    let bytes = input.as_bytes();
+    let mut idx = start;
+    while let Some(len) = input[idx..].find("</") {
+        idx += len;
+        let name_end = idx + 2 + name.len();
+        if bytes.len() >= name_end
+            && bytes[idx + 2..name_end].eq_ignore_ascii_case(name.as_bytes())
+            && bytes
+                .get(name_end)
+                .is_none_or(|byte| is_tag_delimiter(*byte))
+        {
+            return idx;
+        }
+        idx += 2;
+    }
+    bytes.len()
+}
+
+fn find_close(bytes: &[u8], start: usize) -> usize {
+    bytes[start..]
+        .iter()
+        .position(|byte| *byte == b'>')
+        .map_or(bytes.len(), |idx| start + idx + 1)
+}
+
+fn is_tag_delimiter(byte: u8) -> bool {
+    byte.is_ascii_whitespace() || byte == b'/' || byte == b'>'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Uwu;
+
+    //noinspection SpellCheckingInspection
+    #[test]
+    fn assert_uwuify_html() {
+        fn convert(input: &str) -> String {
+            Uwu::builder()
+                .w_replace()
+                .build()
+                .uwuify_html(input, &HtmlOptions::default())
+                .unwrap()
+        }
+
+        assert_eq!(
+            convert(r#"<DIV class="scroll">Hello <b>world</b></DIV>"#),
+            r#"<DIV class="scroll">Hewwo <b>wowwd</b></DIV>"#
+        );
+        assert_eq!(
+            convert("<p>Salt &amp; pepper&nbsp;roll &lt;3 AT&T</p>"),
+            "<p>Sawt &amp; peppew&nbsp;woww &lt;3 AT&T</p>"
+        );
+        assert_eq!(
+            convert("<!DOCTYPE html><!-- rules --><title>Rules</title>"),
+            "<!DOCTYPE html><!-- rules --><title>Wuwes</title>"
+        );
+        assert_eq!(
+            convert("<script>if (l < r) { run('<b>'); }</script><style>p { color: red }</style>"),
+            "<script>if (l < r) { run('<b>'); }</script><style>p { color: red }</style>"
+        );
+        assert_eq!(
+            convert("<pre>let <b>l</b> = r;</pre> <code>l</code> <CODE>r</CODE> real"),
+            "<pre>let <b>l</b> = r;</pre> <code>l</code> <CODE>r</CODE> weaw"
+        );
+        assert_eq!(
+            convert("AT&T rocks, rock&roll &r"),
+            "AT&T wocks, wock&woww &w"
+        );
+        assert_eq!(
+            convert("&#x4C;r &#108;r &#lr; &lr &amp;lr"),
+            "&#x4C;w &#108;w &#ww; &ww &amp;ww"
+        );
+        assert_eq!(convert("l < r and <br/>lr"), "w < w and <br/>ww");
+        assert_eq!(convert("unclosed <a href='lr"), "uncwosed <a href='lr");
+    }
+
+    //noinspection SpellCheckingInspection
+    #[test]
+    fn assert_html_attributes() {
+        let uwu = Uwu::builder().w_replace().build();
+        let mut options = HtmlOptions::new();
+        options
+            .attribute("ALT")
+            .attribute("title")
+            .unskip_element("code");
+
+        assert_eq!(
+            uwu.uwuify_html(
+                r#"<img src="lr.png" alt='A red lorry' title="Red &amp; real" data-l=lr>"#,
+                &options
+            )
+            .unwrap(),
+            r#"<img src="lr.png" alt='A wed wowwy' title="Wed &amp; weaw" data-l=lr>"#
+        );
+        assert_eq!(
+            uwu.uwuify_html("<code>let l = r;</code>", &options)
+                .unwrap(),
+            "<code>wet w = w;</code>"
+        );
+
+        let uwu = Uwu::builder()
+            .emojis(1)
+            .clear_emojis()
+            .add_emoji(">w< ", 1)
+            .build();
+        assert_eq!(
+            uwu.uwuify_html(r#"<p title="Hi, there">Hi, there</p>"#, &options)
+                .unwrap(),
+            r#"<p title="Hi, &gt;w&lt; there">Hi, &gt;w&lt; there</p>"#
+        );
+    }
+}
@@ -26,17 +26,81 @@
 //!     .build();
 //! let uwuified = uwu.uwuify("Hello world!");
 //! ```
+//!
+//! ## `no_std` support
+//!
+//! The library only needs `alloc`. The `std` feature, enabled by default, adds the APIs based on
+//! `std::io` and `std::fs`, like [`Uwu::uwuify_stream`] or [`UwuBuilder::load_dictionary`], and an
+//! OS source of entropy for [`Seed::Random`]. To use it in `no_std` environments, disable the
+//! default features:
+//!
+//! ```toml
+//! uwu-rs = { version = "1.0", default-features = false }
+//! ```
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![warn(missing_docs)]
 
-use aho_corasick::{AhoCorasick, AhoCorasickBuilder, Input};
-use std::io::Write;
-use thiserror::Error;
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io::{Read, Write};
+
+// The pointer shared by the clones of engines and pipelines. Targets without atomic pointers,
+// e.g. `thumbv6m-none-eabi`, fall back to `Rc`, so engines are not `Send` there.
+#[cfg(not(target_has_atomic = "ptr"))]
+pub(crate) use alloc::rc::Rc as Shared;
+#[cfg(target_has_atomic = "ptr")]
+pub(crate) use alloc::sync::Arc as Shared;
 
 mod builder;
+mod case;
+mod chance;
+#[cfg(feature = "serde")]
+mod config;
+mod deuwuify;
 mod dict;
+mod dictionary;
+mod emoji_set;
+mod engine;
+#[cfg(feature = "html")]
+mod html;
+#[cfg(feature = "markdown")]
+mod markdown;
+mod pipeline;
+mod protection;
+mod punctuation;
+mod seed;
+#[cfg(feature = "std")]
+mod stream;
+mod stutter;
+mod style;
+mod tokenizer;
+mod transform;
 
 pub use builder::*;
+pub use case::*;
+pub use chance::*;
+pub use deuwuify::*;
+pub use dictionary::*;
+pub use emoji_set::*;
+pub use engine::*;
+pub use fastrand;
+#[cfg(feature = "html")]
+pub use html::*;
+pub use protection::*;
+pub use punctuation::*;
+pub use seed::*;
+#[cfg(feature = "std")]
+pub use stream::*;
+pub use stutter::*;
+pub use style::*;
+pub use tokenizer::*;
+pub use transform::*;
 
 /// An `Uwu` instance capable of running the uwu algorithm.
 ///
@@ -56,37 +120,81 @@ pub use builder::*;
 ///     .build();
 /// let uwuified = uwu.uwuify("Hello world!");
 /// ```
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "config::UwuConfig", try_from = "config::UwuConfig")
+)]
 pub struct Uwu {
-    /// Enables the lowercase feature, e.g. 'Hello' becomes 'hello'. Beware that the other features
-    /// may misbehave if this feature is disabled.
+    /// Enables the lowercase feature, e.g. 'Hello' becomes 'hello'. The input is case folded, so
+    /// e.g. 'Straße' becomes 'strasse'. If this feature is disabled, the other features preserve
+    /// the case of the input, e.g. 'Hello' becomes 'Hewwo'.
     pub lowercase: bool,
+    /// Specifies the locale rules used by the lowercase feature, e.g. `Locale::Turkic` converts 'I'
+    /// to 'ı'.
+    pub locale: Locale,
     /// Enables expression replacement, e.g. 'what' becomes 'nani'.
     pub expressions: bool,
+    /// Specifies the expressions replaced by the expressions feature.
+    pub dictionary: Dictionary,
+    /// Enables the nya feature, which adds a 'y' between an 'n' and a vowel, e.g. 'nice anime'
+    /// becomes 'nyice anyime'.
+    pub nya: bool,
+    /// Specifies the words left alone by the nya feature, ignoring case, e.g. 'canon'. Defaults to
+    /// words whose 'n' is followed by a silent 'e', e.g. 'one' and 'done'.
+    pub nya_exceptions: Vec<String>,
     /// Enables replacement of 'l' and 'r' with 'w', e.g. 'lovely' becomes 'wovewy'.
     pub w_replace: bool,
-    /// Enables stutter, e.g. 'hello' becomes 'h-hello'.
+    /// Enables stutter, e.g. 'hello' becomes 'h-hello', for words after whitespace or an opening
+    /// bracket or quote.
     pub stutter: bool,
-    /// Specifies how frequent the stutter is. A value of 1 will add stutter to every word, whereas
-    /// a value of 2 will add stutter every 2 words on average.
-    pub stutter_chance: u8,
+    /// Specifies how frequent the stutter is. A chance of 1 in 1 will add stutter to every word,
+    /// whereas a chance of 1 in 2 or 50% will add stutter every 2 words on average.
+    pub stutter_chance: Chance,
+    /// Specifies which words stutter and how, e.g. skipping short words or repeating the stutter.
+    pub stutter_options: StutterOptions,
     /// Enables adding emojis after punctuation, e.g. 'goodbye.' becomes 'goodbye. OwO'.
     pub emojis: bool,
-    /// Specifies how frequently emojis are added. A value of 1 will add emojis after every
-    /// punctuation, whereas a value of 2 will add emojis every 2 punctuation marks on average.
-    pub emojis_chance: u8,
+    /// Specifies how frequently emojis are added. A chance of 1 in 1 will add emojis after every
+    /// punctuation, whereas a chance of 1 in 2 or 50% will add emojis every 2 punctuation marks on
+    /// average.
+    pub emojis_chance: Chance,
+    /// Specifies the emojis added by the emojis feature.
+    pub emoji_set: EmojiSet,
+    /// Specifies the punctuation marks after which the emojis feature adds emojis.
+    pub punctuation_set: PunctuationSet,
+    /// Specifies the kinds of tokens passed through verbatim, e.g. URLs or @mentions. Defaults to
+    /// none, like the builder, so the output of existing configurations does not change.
+    pub protection: Protection,
+    /// Specifies the words passed through verbatim, e.g. brand names like 'Rust'. They only match
+    /// whole words, with the same case.
+    pub protected_words: Vec<String>,
+    /// Specifies how the random number generator used by the stutter and emojis features is
+    /// seeded. Defaults to a fixed seed, so the same input always produces the same output.
+    pub seed: Seed,
 }
 
 impl Default for Uwu {
     fn default() -> Self {
         Self {
             lowercase: true,
+            locale: Locale::Root,
             expressions: true,
+            dictionary: Dictionary::default(),
+            nya: true,
+            nya_exceptions: dict::NYA_EXCEPTIONS.map(String::from).to_vec(),
             w_replace: true,
             stutter: true,
-            stutter_chance: 4,
+            stutter_chance: Chance::one_in(4),
+            stutter_options: StutterOptions::default(),
             emojis: true,
-            emojis_chance: 1,
+            emojis_chance: Chance::one_in(1),
+            emoji_set: EmojiSet::default(),
+            punctuation_set: PunctuationSet::default(),
+            protection: Protection::NONE,
+            protected_words: Vec::new(),
+            seed: Seed::default(),
         }
     }
 }
@@ -102,156 +210,240 @@ impl Uwu {
         UwuBuilder::new()
     }
 
-    /// Converts the input text into an uwuified version.
+    /// Validates the configuration, e.g. that the chance of an enabled feature is not 0, without
+    /// compiling it. Every error names the offending field.
     ///
     /// Example:
     /// ```
-    /// let uwuified = uwu_rs::Uwu::new().uwuify("Hello world!");
+    /// let mut uwu = uwu_rs::Uwu::new();
+    /// uwu.stutter_chance = uwu_rs::Chance::one_in(0);
+    /// assert!(uwu.validate().is_err());
     /// ```
-    pub fn uwuify<S: AsRef<str>>(&self, input: S) -> Result<String, UwuError> {
-        let mut input = input.as_ref().to_owned();
-
-        if self.lowercase {
-            input = input.to_ascii_lowercase();
-        }
-
-        let mut buf = input.into_bytes();
-        // Pad input with spaces so features that only operate after spaces work on the first and
-        // last word.
-        buf.insert(0, b' ');
-        buf.push(b' ');
-
-        if self.expressions {
-            buf = Self::do_expressions(buf)?;
-        }
-        if self.w_replace {
-            buf = Self::do_w_replace(buf)?;
-        }
+    pub fn validate(&self) -> Result<(), UwuError> {
         if self.stutter {
-            buf = self.do_stutter(buf)?;
-        }
-        if self.emojis {
-            buf = self.do_emojis(buf)?;
-        }
-
-        // Remove the padding added in the start
-        if let Some(last) = buf.last() {
-            if *last == b' ' {
-                buf.pop();
+            // The every Nth word mode ignores the chance
+            if self.stutter_options.mode == StutterMode::Random {
+                self.stutter_chance
+                    .validate("stutter_chance", "stutter is")?;
             }
+            self.stutter_options.validate()?;
         }
-        if let Some(first) = buf.first() {
-            if *first == b' ' {
-                buf.remove(0);
-            }
+        if self.emojis {
+            self.emojis_chance.validate("emojis_chance", "emojis are")?;
+            self.emoji_set.validate()?;
+            self.punctuation_set.validate()?;
         }
+        self.dictionary.validate()?;
+        protection::validate_words(&self.protected_words)?;
+        Ok(())
+    }
 
-        let output = String::from_utf8_lossy(&buf).to_string();
-        Ok(output)
+    /// Compiles this instance into an [`UwuEngine`], which can be reused to uwuify many inputs
+    /// without rebuilding its string matchers on every call.
+    pub fn compile(&self) -> Result<UwuEngine, UwuError> {
+        UwuEngine::new(self.clone())
     }
 
-    fn do_expressions(input: Vec<u8>) -> Result<Vec<u8>, UwuError> {
-        let mut buf = Vec::with_capacity(input.len());
-        let matcher = AhoCorasick::new(dict::EXPRESSIONS)?;
-        matcher.try_stream_replace_all(
-            input.as_slice(),
-            &mut buf,
-            dict::EXPRESSIONS_REPLACE.as_slice(),
-        )?;
-        Ok(buf)
+    /// Converts the input text into an uwuified version.
+    ///
+    /// When converting many inputs, prefer compiling an [`UwuEngine`] with [`Uwu::compile`] once
+    /// and reusing it.
+    ///
+    /// Example:
+    /// ```
+    /// let uwuified = uwu_rs::Uwu::new().uwuify("Hello world!");
+    /// ```
+    pub fn uwuify<S: AsRef<str>>(&self, input: S) -> Result<String, UwuError> {
+        self.compile()?.uwuify(input)
     }
 
-    fn do_w_replace(mut input: Vec<u8>) -> Result<Vec<u8>, UwuError> {
-        input.iter_mut().for_each(|byte| {
-            if matches!(byte, b'l' | b'r') {
-                *byte = b'w';
-            }
-        });
-        Ok(input)
+    /// Converts the input text into an uwuified version, using a caller-owned random number
+    /// generator instead of the configured [`Seed`]. The generator is advanced on every call, so
+    /// repeated calls produce different outputs.
+    ///
+    /// Example:
+    /// ```
+    /// let mut rng = uwu_rs::fastrand::Rng::with_seed(42);
+    /// let uwuified = uwu_rs::Uwu::new().uwuify_with_rng("Hello world!", &mut rng);
+    /// ```
+    pub fn uwuify_with_rng<S: AsRef<str>>(
+        &self,
+        input: S,
+        rng: &mut fastrand::Rng,
+    ) -> Result<String, UwuError> {
+        self.compile()?.uwuify_with_rng(input, rng)
     }
 
-    fn do_stutter(&self, input: Vec<u8>) -> Result<Vec<u8>, UwuError> {
-        if input.len() < 2 {
-            return Ok(input);
-        }
+    /// Converts the prose of a CommonMark document into an uwuified version, leaving its structure,
+    /// code, link targets, HTML and front matter untouched.
+    ///
+    /// Example:
+    /// ```
+    /// let uwuified = uwu_rs::Uwu::new().uwuify_markdown("# Hello\n\n```\nlet l = r;\n```\n");
+    /// ```
+    #[cfg(feature = "markdown")]
+    pub fn uwuify_markdown<S: AsRef<str>>(&self, input: S) -> Result<String, UwuError> {
+        self.compile()?.uwuify_markdown(input)
+    }
 
-        let mut buf: Vec<u8> = Vec::with_capacity(input.len());
-        let mut rng = Uwu::create_rng();
-
-        let mut prev_idx = 0;
-        for mut idx in 0..input.len() - 1 {
-            if input[idx] == b' '
-                && input[idx + 1].is_ascii_alphabetic()
-                && rng.u8(0..self.stutter_chance) == 0
-            {
-                idx += 1;
-                let section = &input[prev_idx..idx];
-                let ch = input[idx];
-                buf.write_all(section)?;
-                buf.write_all(&[ch])?;
-                buf.write_all(&[b'-'])?;
-                prev_idx = idx;
-            }
-        }
+    /// Converts the visible text of an HTML document into an uwuified version, leaving its markup,
+    /// character references and the elements skipped by the options untouched.
+    ///
+    /// Example:
+    /// ```
+    /// let options = uwu_rs::HtmlOptions::new();
+    /// let uwuified = uwu_rs::Uwu::new().uwuify_html("<p>Hello <code>world</code></p>", &options);
+    /// ```
+    #[cfg(feature = "html")]
+    pub fn uwuify_html<S: AsRef<str>>(
+        &self,
+        input: S,
+        options: &HtmlOptions,
+    ) -> Result<String, UwuError> {
+        self.compile()?.uwuify_html(input, options)
+    }
 
-        // Dump remaining
-        buf.write_all(&input[prev_idx..])?;
+    /// Converts the input text into an uwuified version, passing the given spans through verbatim,
+    /// e.g. usernames or quoted code.
+    ///
+    /// Example:
+    /// ```
+    /// use uwu_rs::{ProtectedSpan, Uwu};
+    ///
+    /// let uwuified = Uwu::new().uwuify_protecting("Hello Laura", &[ProtectedSpan::Bytes(6..11)]);
+    /// ```
+    pub fn uwuify_protecting<S: AsRef<str>>(
+        &self,
+        input: S,
+        spans: &[ProtectedSpan],
+    ) -> Result<String, UwuError> {
+        self.compile()?.uwuify_protecting(input, spans)
+    }
 
-        Ok(buf)
+    /// Converts uwuified text back into a best-effort plain version, reversing the emojis and
+    /// expressions of this instance. See [`Deuwuifier`] to restore many texts.
+    ///
+    /// Example:
+    /// ```
+    /// let deuwuified = uwu_rs::Uwu::new().deuwuify("h-hewwo wowwd! OwO").unwrap();
+    /// assert_eq!(deuwuified.text, "hello world!");
+    /// ```
+    pub fn deuwuify<S: AsRef<str>>(&self, input: S) -> Result<Deuwuified, UwuError> {
+        Ok(Deuwuifier::new(self)?.deuwuify(input))
     }
 
-    fn do_emojis(&self, input: Vec<u8>) -> Result<Vec<u8>, UwuError> {
-        let matcher = AhoCorasickBuilder::new().build(dict::PUNCTUATION)?;
-        let matches = matcher
-            .try_find_iter(Input::new(&input))?
-            .map(|mat| mat.end())
-            .collect::<Vec<usize>>();
-        if matches.is_empty() {
-            return Ok(input);
-        }
+    /// Reads text from the reader and writes the uwuified version to the writer, using a bounded
+    /// amount of memory. The output is identical to calling [`Uwu::uwuify`] with the whole input,
+    /// as long as the seed does not depend on the input (see [`Seed::Input`]), except in rare cases
+    /// within runs of more than 64 KiB without whitespace.
+    ///
+    /// Returns the number of bytes written.
+    ///
+    /// Example:
+    /// ```
+    /// let mut output = Vec::new();
+    /// uwu_rs::Uwu::new().uwuify_stream("Hello world!".as_bytes(), &mut output).unwrap();
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn uwuify_stream<R: Read, W: Write>(&self, reader: R, writer: W) -> Result<u64, UwuError> {
+        self.compile()?.uwuify_stream(reader, writer)
+    }
+}
 
-        let mut buf = Vec::with_capacity(input.len());
-        let mut rng = Uwu::create_rng();
+/// A Uwu error.
+#[derive(Debug)]
+pub enum UwuError {
+    /// Error building string matcher
+    StringMatcherBuild(aho_corasick::BuildError),
+    /// Error in string match
+    StringMatcherMatch(aho_corasick::MatchError),
+    /// Error in an expressions dictionary
+    InvalidDictionary(String),
+    /// Error when an expression can never be replaced because of another expression
+    ExpressionConflict(String, String),
+    /// Error in an emoji set
+    InvalidEmojiSet(String),
+    /// Error in a punctuation set
+    InvalidPunctuation(String),
+    /// Error in a protected word or span
+    InvalidProtection(String),
+    /// Error in a configuration, e.g. a chance of 0
+    InvalidConfig(String),
+    /// Style not found in a registry
+    UnknownStyle(String),
+    /// Error in the stages of a pipeline, e.g. an unknown stage name
+    InvalidPipeline(String),
+    /// IO error
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    /// Unknown error
+    Unknown(Box<dyn core::error::Error + Send>),
+}
 
-        let mut prev_idx = 0;
-        for idx in matches {
-            if rng.u8(0..self.emojis_chance) != 0 {
-                continue;
+impl fmt::Display for UwuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UwuError::StringMatcherBuild(err) => write!(f, "string matcher build error: {err}"),
+            UwuError::StringMatcherMatch(err) => write!(f, "string matcher match error: {err}"),
+            UwuError::InvalidDictionary(msg) => write!(f, "invalid dictionary: {msg}"),
+            UwuError::ExpressionConflict(shadowed, by) => {
+                write!(
+                    f,
+                    "expression '{shadowed}' is shadowed by expression '{by}'"
+                )
             }
-            let section = &input[prev_idx..idx];
-            let emoji = rng.choice(dict::EMOJIS).unwrap_or("uwu");
-            buf.write_all(section)?;
-            buf.write_all(emoji.as_bytes())?;
-            prev_idx = idx;
+            UwuError::InvalidEmojiSet(msg) => write!(f, "invalid emoji set: {msg}"),
+            UwuError::InvalidPunctuation(msg) => write!(f, "invalid punctuation: {msg}"),
+            UwuError::InvalidProtection(msg) => write!(f, "invalid protection: {msg}"),
+            UwuError::InvalidConfig(msg) => write!(f, "invalid configuration: {msg}"),
+            UwuError::UnknownStyle(name) => write!(f, "unknown style: {name}"),
+            UwuError::InvalidPipeline(msg) => write!(f, "invalid pipeline: {msg}"),
+            #[cfg(feature = "std")]
+            UwuError::Io(err) => write!(f, "IO error: {err}"),
+            UwuError::Unknown(err) => fmt::Display::fmt(err, f),
         }
+    }
+}
 
-        // Dump remaining
-        buf.write_all(&input[prev_idx..])?;
+impl core::error::Error for UwuError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            // The errors of the string matcher only implement `Error` with `std`
+            #[cfg(feature = "std")]
+            UwuError::StringMatcherBuild(err) => Some(err),
+            #[cfg(feature = "std")]
+            UwuError::StringMatcherMatch(err) => Some(err),
+            #[cfg(feature = "std")]
+            UwuError::Io(err) => Some(err),
+            UwuError::Unknown(err) => err.source(),
+            _ => None,
+        }
+    }
+}
 
-        Ok(buf)
+impl From<aho_corasick::BuildError> for UwuError {
+    fn from(err: aho_corasick::BuildError) -> Self {
+        UwuError::StringMatcherBuild(err)
     }
+}
 
-    fn create_rng() -> fastrand::Rng {
-        let seed = 75777521; // 'uwu!' = 75 77 75 21
-        fastrand::Rng::with_seed(seed)
+impl From<aho_corasick::MatchError> for UwuError {
+    fn from(err: aho_corasick::MatchError) -> Self {
+        UwuError::StringMatcherMatch(err)
     }
 }
 
-/// A Uwu error.
-#[derive(Error, Debug)]
-pub enum UwuError {
-    /// Error building string matcher
-    #[error("string matcher build error: {0}")]
-    StringMatcherBuild(#[from] aho_corasick::BuildError),
-    /// Error in string match
-    #[error("string matcher match error: {0}")]
-    StringMatcherMatch(#[from] aho_corasick::MatchError),
-    /// IO error
-    #[error("IO error: {0}")]
-    Io(#[from] std::io::Error),
-    /// Unknown error
-    #[error(transparent)]
-    Unknown(#[from] Box<dyn std::error::Error + Send>),
+#[cfg(feature = "std")]
+impl From<std::io::Error> for UwuError {
+    fn from(err: std::io::Error) -> Self {
+        UwuError::Io(err)
+    }
+}
+
+impl From<Box<dyn core::error::Error + Send>> for UwuError {
+    fn from(err: Box<dyn core::error::Error + Send>) -> Self {
+        UwuError::Unknown(err)
+    }
 }
 
 /// Converts text to an uwuified version. This is a utility method for using
@@ -261,6 +453,14 @@ pub fn uwuify<S: AsRef<str>>(input: S) -> Result<String, UwuError> {
     uwu.uwuify(input)
 }
 
+/// Reads text from the reader and writes the uwuified version to the writer. This is a utility
+/// method for using `Uwu::new().uwuify_stream(reader, writer)`.
+#[cfg(feature = "std")]
+pub fn uwuify_stream<R: Read, W: Write>(reader: R, writer: W) -> Result<u64, UwuError> {
+    let uwu = Uwu::new();
+    uwu.uwuify_stream(reader, writer)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -285,13 +485,192 @@ mod tests {
         );
     }
 
+    //noinspection SpellCheckingInspection
+    #[test]
+    fn assert_uwuify_unicode() {
+        fn convert(uwu: &Uwu, input: &str) -> String {
+            uwu.uwuify(input).unwrap()
+        }
+
+        let uwu = Uwu::builder().lowercase().w_replace().build();
+        assert_eq!(convert(&uwu, "ÉCOLE"), "écowe");
+        assert_eq!(
+            convert(&uwu, "Straße STRAẞE ſtraße"),
+            "stwasse stwasse stwasse"
+        );
+        assert_eq!(convert(&uwu, "ΟΔΥΣΣΕΥΣ Οδυσσεύς"), "οδυσσευσ οδυσσεύσ");
+        assert_eq!(convert(&uwu, "ﬁre ﬂower µ Ꭰꭰ"), "fiwe fwowew μ ᎠᎠ");
+        assert_eq!(convert(&uwu, "ПРИВЕТ World"), "привет wowwd");
+        assert_eq!(convert(&uwu, "DIYARBAKIR ılık"), "diyawbakiw ıwık");
+
+        let uwu = Uwu::builder()
+            .lowercase()
+            .locale(Locale::Turkic)
+            .w_replace()
+            .build();
+        assert_eq!(convert(&uwu, "DİYARBAKIR ılık"), "diyawbakıw ıwık");
+
+        // Byte-based features must not split multi-byte characters
+        assert_eq!(
+            convert(&Uwu::new(), "ÉLÉGANT, ÜBER CAFÉ! ΚΑΛΗΜΕΡΑ"),
+            "éwégant, o.O übew c-café! ^•ﻌ•^ καλημερα"
+        );
+    }
+
+    //noinspection SpellCheckingInspection
+    #[test]
+    fn assert_stutter_graphemes() {
+        let uwu = Uwu::builder().stutter(1).build();
+        assert_eq!(
+            uwu.uwuify("élan über привет こんにちは").unwrap(),
+            "é-élan ü-über п-привет こ-こんにちは"
+        );
+        // Combining marks and modifiers are part of the first grapheme
+        assert_eq!(
+            uwu.uwuify("e\u{301}lan a\u{308}\u{301}b (ñ)").unwrap(),
+            "e\u{301}-e\u{301}lan a\u{308}\u{301}-a\u{308}\u{301}b (ñ-ñ)"
+        );
+        assert_eq!(uwu.uwuify("é").unwrap(), "é-é");
+        assert_eq!(uwu.uwuify("x\u{1f3fd}y").unwrap(), "x\u{1f3fd}-x\u{1f3fd}y");
+        // So are vowel signs and Hangul jamo, e.g. in Bengali, Devanagari and decomposed Korean
+        assert_eq!(uwu.uwuify("কিছু हिंदी").unwrap(), "কি-কিছু हिं-हिंदी");
+        assert_eq!(
+            uwu.uwuify("\u{1112}\u{1161}\u{11ab}\u{1100}\u{116e}\u{11a8}")
+                .unwrap(),
+            "\u{1112}\u{1161}\u{11ab}-\u{1112}\u{1161}\u{11ab}\u{1100}\u{116e}\u{11a8}"
+        );
+        // Emojis and digits are not words that stutter
+        assert_eq!(uwu.uwuify("👋🏽 42 ✨").unwrap(), "👋🏽 42 ✨");
+    }
+
+    //noinspection SpellCheckingInspection
+    #[test]
+    fn assert_nya() {
+        let uwu = Uwu::builder().nya().build();
+        assert_eq!(uwu.uwuify("nice anime").unwrap(), "nyice anyime");
+        assert_eq!(uwu.uwuify("nth NO No nO, n").unwrap(), "nth NYO Nyo nyO, n");
+
+        let uwu = Uwu::builder()
+            .nya()
+            .add_nya_exception("Banana")
+            .add_nya_exception("none")
+            .build();
+        assert_eq!(
+            uwu.uwuify("BANANA bananas, None anon").unwrap(),
+            "BANANA banyanyas, None anyon"
+        );
+        // Only whole words are exceptions, wherever they are
+        assert_eq!(
+            uwu.uwuify("Cute  . banana, bananana").unwrap(),
+            "Cute  . banana, banyanyanya"
+        );
+
+        // The builder starts with the same exceptions as the default instance
+        assert_eq!(
+            Uwu::builder().nya().build().nya_exceptions,
+            Uwu::new().nya_exceptions
+        );
+        assert_eq!(uwu.uwuify("no one").unwrap(), "nyo one");
+
+        // Expression replacements are never nya'd
+        let uwu = Uwu::builder().expressions().nya().build();
+        assert_eq!(uwu.uwuify("What, no").unwrap(), "Nani, nyo");
+        assert_eq!(uwu.uwuify("WHAT NOW").unwrap(), "NANI NYOW");
+    }
+
+    //noinspection SpellCheckingInspection
+    #[test]
+    fn assert_uwuify_preserve_case() {
+        fn convert(input: &str) -> String {
+            Uwu::builder()
+                .expressions()
+                .nya()
+                .w_replace()
+                .build()
+                .uwuify(input)
+                .unwrap()
+        }
+
+        assert_eq!(convert("Hello WORLD"), "Hewwo WOWWD");
+        assert_eq!(convert("What"), "Nani");
+        assert_eq!(convert("WHAT IS LOVE"), "NANI IS WUV");
+        assert_eq!(convert("what a Cute kitten"), "nani a Kawaii~ kitten");
+        assert_eq!(convert("Very Nice"), "Vewy Nyice");
+        assert_eq!(convert("VERY NICE"), "VEWY NYICE");
+        assert_eq!(convert("very nice"), "vewy nyice");
+
+        let uwu = Uwu {
+            lowercase: false,
+            ..Uwu::default()
+        };
+        assert_eq!(
+            uwu.uwuify("STOP SHOUTING, PLEASE").unwrap(),
+            "STOP SHOUTING, o.O P-PWEASE"
+        );
+    }
+
+    //noinspection SpellCheckingInspection
+    #[test]
+    fn assert_whitespace() {
+        // Words stutter after any whitespace or opening bracket, and the whitespace is kept as is
+        let uwu = Uwu::builder().w_replace().stutter(1).build();
+        assert_eq!(
+            uwu.uwuify(" hello\tworld\r\n(lovely) “rare”\u{a0}rock\u{3000}run ")
+                .unwrap(),
+            " h-hewwo\tw-wowwd\r\n(w-wovewy) “w-wawe”\u{a0}w-wock\u{3000}w-wun "
+        );
+        assert_eq!(
+            uwu.uwuify("don't [be] shy").unwrap(),
+            "d-don't [b-be] s-shy"
+        );
+
+        let uwu = Uwu::new();
+        for (input, expected) in [
+            ("", ""),
+            (" ", " "),
+            ("\n\n", "\n\n"),
+            ("  hi.  ", "  hi. o.O  "),
+            ("hi.", "hi. o.O"),
+            ("hi.\r\n", "hi. o.O\r\n"),
+            ("\tyes\t", "\tyes\t"),
+        ] {
+            assert_eq!(uwu.uwuify(input).unwrap(), expected, "{input:?}");
+        }
+    }
+
+    #[test]
+    fn assert_seed() {
+        let input = "the quick brown fox jumps over the lazy dog. the end.";
+        let mut uwu = Uwu::builder();
+        uwu.stutter(2).emojis(2);
+
+        let fixed = uwu.build();
+        assert_eq!(fixed.uwuify(input).unwrap(), fixed.uwuify(input).unwrap());
+        let other_fixed = uwu.seed(Seed::Fixed(1)).build();
+        assert_ne!(
+            fixed.uwuify(input).unwrap(),
+            other_fixed.uwuify(input).unwrap()
+        );
+
+        let from_input = uwu.seed(Seed::Input).build();
+        assert_eq!(
+            from_input.uwuify(input).unwrap(),
+            from_input.uwuify(input).unwrap()
+        );
+
+        let mut rng = fastrand::Rng::with_seed(DEFAULT_SEED);
+        let first = fixed.uwuify_with_rng(input, &mut rng).unwrap();
+        let second = fixed.uwuify_with_rng(input, &mut rng).unwrap();
+        assert_ne!(first, second);
+    }
+
     #[test]
     fn assert_rng() {
         fn calc_avg<F>(mut func: F, rounds: usize) -> f64
         where
             F: FnMut(&mut fastrand::Rng) -> bool,
         {
-            let mut rng = fastrand::Rng::with_seed(75777521); // 'uwu!' = 75 77 75 21
+            let mut rng = fastrand::Rng::with_seed(DEFAULT_SEED);
             let mut positives = 0;
             for _ in 0..rounds {
                 if func(&mut rng) {
@@ -0,0 +1,119 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use pulldown_cmark::{Event, LinkType, Options, Parser, Tag, TagEnd};
+
+/// The CommonMark extensions recognised in Markdown documents. Front matter must be parsed so it
+/// is not mistaken for prose, and tables so their cells are.
+const OPTIONS: Options = Options::ENABLE_TABLES
+    .union(Options::ENABLE_YAML_STYLE_METADATA_BLOCKS)
+    .union(Options::ENABLE_PLUSES_DELIMITED_METADATA_BLOCKS);
+
+/// The characters escaped in the text added to Markdown documents, since they may start inline
+/// syntax, e.g. the '*' in '*blushes*'.
+const SPECIAL: &[u8] = b"\\`*_[]<>~|";
+
+/// Escapes the characters of the text that may start inline syntax.
+pub(crate) fn escape(text: &str, out: &mut Vec<u8>) {
+    for byte in text.bytes() {
+        if SPECIAL.contains(&byte) {
+            out.push(b'\\');
+        }
+        out.push(byte);
+    }
+}
+
+/// Finds the ranges of the input with prose, i.e. text rendered as written. Adjacent text is
+/// merged into a single range.
+///
+/// Text that is not written as is in the input, e.g. entities like '&amp;', is skipped, as well as
+/// code blocks, front matter and autolinks, whose text is also their target.
+pub(crate) fn prose(input: &str) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    let mut verbatim = false;
+    let mut after_text = false;
+    for (event, range) in Parser::new_ext(input, OPTIONS).into_offset_iter() {
+        let is_text = matches!(event, Event::Text(_));
+        match event {
+            Event::Start(Tag::CodeBlock(_) | Tag::MetadataBlock(_)) => verbatim = true,
+            Event::Start(Tag::Link {
+                link_type: LinkType::Autolink | LinkType::Email,
+                ..
+            }) => verbatim = true,
+            Event::End(TagEnd::CodeBlock | TagEnd::MetadataBlock(_) | TagEnd::Link) => {
+                verbatim = false
+            }
+            Event::Text(text) if !verbatim && *text == input[range.clone()] => {
+                match ranges.last_mut() {
+                    Some(last) if after_text && last.end == range.start => last.end = range.end,
+                    _ => ranges.push(range),
+                }
+            }
+            _ => {}
+        }
+        after_text = is_text;
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Uwu;
+
+    //noinspection SpellCheckingInspection
+    #[test]
+    fn assert_uwuify_markdown() {
+        fn convert(input: &str) -> String {
+            Uwu::builder()
+                .w_replace()
+                .build()
+                .uwuify_markdown(input)
+                .unwrap()
+        }
+
+        assert_eq!(convert("Hello *world*"), "Hewwo *wowwd*");
+        assert_eq!(
+            convert("Call `let l = r;` for real"),
+            "Caww `let l = r;` fow weaw"
+        );
+        assert_eq!(
+            convert(
+                "Read [the rules](https://rust-lang.org/rules \"Rules\") or <https://rules.rs>"
+            ),
+            "Wead [the wuwes](https://rust-lang.org/rules \"Rules\") ow <https://rules.rs>"
+        );
+        assert_eq!(
+            convert("![a red lorry](lorry.png) <!-- really -->"),
+            "![a wed wowwy](lorry.png) <!-- really -->"
+        );
+        assert_eq!(convert("Salt &amp; pepper"), "Sawt &amp; peppew");
+        assert_eq!(
+            convert(
+                "---\ntitle: Rules\n---\n# Rules\n\n```rust\nlet l = r;\n```\n\n    let l = r;\n"
+            ),
+            "---\ntitle: Rules\n---\n# Wuwes\n\n```rust\nlet l = r;\n```\n\n    let l = r;\n"
+        );
+        assert_eq!(
+            convert("| left | right |\n|------|-------|\n| l | r |\n"),
+            "| weft | wight |\n|------|-------|\n| w | w |\n"
+        );
+        assert_eq!(
+            convert("* lists\n* are\n\n> quotes are\n> prose\n"),
+            "* wists\n* awe\n\n> quotes awe\n> pwose\n"
+        );
+    }
+
+    //noinspection SpellCheckingInspection
+    #[test]
+    fn assert_markdown_emojis() {
+        let uwu = Uwu::builder()
+            .emojis(1)
+            .clear_emojis()
+            .add_emoji("*blushes* ", 1)
+            .build();
+        assert_eq!(
+            uwu.uwuify_markdown("Hi, *there*.").unwrap(),
+            "Hi, \\*blushes\\* *there*. \\*blushes\\*"
+        );
+    }
+}
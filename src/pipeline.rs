@@ -0,0 +1,1186 @@
+use aho_corasick::automaton::{Automaton, StateID};
+use aho_corasick::{Anchored, PatternID};
+
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::case::Case;
+use crate::dictionary::{is_word_byte, BOUNDARY};
+use crate::seed::FeatureRngs;
+use crate::tokenizer::{first_grapheme, tokenize};
+use crate::{Locale, UwuEngine};
+
+/// The state of the uwu algorithm while it runs over an input.
+///
+/// Every feature is implemented as a small state machine, and each byte of the input flows through
+/// all of them in a single left-to-right pass:
+///
+/// 1. lowercase, one word at a time so context-dependent mappings (e.g. final sigma) still work
+/// 2. expressions, by stepping through the expressions automaton
+/// 3. nya, which holds back an 'n' until the byte that follows it, or whole words when there are
+///    exceptions
+/// 4. w replace
+/// 5. stutter, which looks at the previously emitted character, and holds back the first
+///    grapheme of a word until the character that ends it
+/// 6. emojis, by stepping through the punctuation automaton
+///
+/// While no feature is in the middle of a match, bytes that cannot start one are copied straight
+/// into the output.
+///
+/// The input can be pushed in multiple pieces, as long as every piece ends at a word boundary, and
+/// the output is the same as pushing the whole input at once.
+pub(crate) struct Pipeline {
+    rngs: FeatureRngs,
+    markup: Markup,
+    started: bool,
+
+    /// The current state of the expressions automaton.
+    expressions_sid: StateID,
+    /// Bytes that may still be part of an expression, and so were not emitted yet.
+    expressions_pending: Vec<u8>,
+    /// With leftmost match semantics, the best match found so far and where it ends in the
+    /// pending bytes, while looking for a better one.
+    expressions_candidate: Option<(PatternID, usize)>,
+    /// Whether the previous byte was part of a word, to find the start and end of words.
+    expressions_word: bool,
+    /// An expression match whose case depends on the byte that follows it.
+    expressions_deferred: Option<PatternID>,
+    /// The text matched by the deferred expression.
+    expressions_matched: Vec<u8>,
+
+    /// The bytes held back by the nya step: an 'n' that may be followed by a vowel, or the whole
+    /// word when it may be an exception.
+    nya_held: Vec<u8>,
+    /// Whether the held bytes are a whole word that may be an exception.
+    nya_exception: bool,
+    /// Whether the previous byte of the nya step was part of a word, to find the start of words.
+    nya_word: bool,
+
+    /// Whether the previous character emitted by the w replace step was a word boundary, e.g. a
+    /// space or an opening bracket.
+    stutter_after_space: bool,
+    /// The character emitted by the w replace step, while its bytes are being emitted.
+    stutter_char: CharDecoder,
+    /// The bytes held back at the start of a word: the first grapheme of a word that stutters,
+    /// followed by the character being decoded, or the whole word when the stutter rules need
+    /// it.
+    stutter_held: Vec<u8>,
+    /// The length of the grapheme that stutters in the held bytes, or 0 while the first
+    /// character of the word is being decoded, or when the whole word is held.
+    stutter_grapheme: usize,
+    /// How many times the grapheme that stutters is repeated.
+    stutter_repeats: u8,
+    /// The number of words that could stutter so far, for the every Nth word mode.
+    stutter_count: u64,
+
+    /// The current state of the punctuation automaton.
+    emojis_sid: StateID,
+    /// Whether the previous bytes end with punctuation, so an emoji may be added depending on the
+    /// byte that follows.
+    emojis_armed: bool,
+    /// Whether the punctuation the previous bytes end with adds emojis even when it is not followed
+    /// by whitespace.
+    emojis_standalone: bool,
+    /// The bytes of a multi-byte character that follows punctuation, held back until it is known
+    /// whether it is whitespace.
+    emojis_held: Vec<u8>,
+
+    /// Whether the first output byte should be dropped if it is a space, i.e. it is padding.
+    skip_space: bool,
+}
+
+impl Pipeline {
+    pub(crate) fn new(engine: &UwuEngine, rngs: FeatureRngs) -> Self {
+        Self {
+            rngs,
+            markup: Markup::Plain,
+            started: false,
+            expressions_sid: engine.expressions_start,
+            expressions_pending: Vec::with_capacity(engine.expressions.max_pattern_len()),
+            expressions_candidate: None,
+            expressions_word: false,
+            expressions_deferred: None,
+            expressions_matched: Vec::new(),
+            nya_held: Vec::new(),
+            nya_exception: false,
+            nya_word: false,
+            stutter_after_space: false,
+            stutter_char: CharDecoder::default(),
+            stutter_held: Vec::new(),
+            stutter_grapheme: 0,
+            stutter_repeats: 0,
+            stutter_count: 0,
+            emojis_sid: engine.punctuation_start,
+            emojis_armed: false,
+            emojis_standalone: false,
+            emojis_held: Vec::new(),
+            skip_space: true,
+        }
+    }
+
+    /// Escapes the text added by the pipeline, e.g. emojis, for the given markup language.
+    pub(crate) fn markup(mut self, markup: Markup) -> Self {
+        self.markup = markup;
+        self
+    }
+
+    /// Uwuifies the input into the output. Some bytes may be held back until more input is pushed
+    /// or the pipeline is finished.
+    pub(crate) fn push(&mut self, engine: &UwuEngine, input: &str, out: &mut Vec<u8>) {
+        self.start(engine, out);
+
+        if engine.uwu.protection.is_none() && engine.protected_words.is_none() {
+            self.push_text(engine, input, out);
+            return;
+        }
+        // Protected tokens and words never contain whitespace, so they are never split between
+        // pieces
+        let protected = engine.protected_ranges(input, &[]).unwrap_or_default();
+        self.push_protected(engine, input, &protected, out);
+    }
+
+    /// Uwuifies the input into the output, passing the protected ranges through verbatim. The
+    /// ranges must be sorted and must not overlap.
+    pub(crate) fn push_protected(
+        &mut self,
+        engine: &UwuEngine,
+        input: &str,
+        protected: &[Range<usize>],
+        out: &mut Vec<u8>,
+    ) {
+        self.start(engine, out);
+
+        let mut copied = 0;
+        for range in protected {
+            self.push_text(engine, &input[copied..range.start], out);
+            self.push_verbatim(engine, &input[range.clone()], out);
+            copied = range.end;
+        }
+        self.push_text(engine, &input[copied..], out);
+    }
+
+    fn push_text(&mut self, engine: &UwuEngine, input: &str, out: &mut Vec<u8>) {
+        // ASCII is lowercased while being fed, except for the Turkic 'I', which becomes 'ı'
+        let turkic = engine.uwu.locale == Locale::Turkic;
+        let is_ascii = |text: &str| text.is_ascii() && !(turkic && text.contains('I'));
+        if !engine.uwu.lowercase || is_ascii(input) {
+            self.feed_all(engine, input.as_bytes(), out);
+            return;
+        }
+
+        // Case folding maps every character on its own, except for the decomposed Turkic 'İ', so
+        // words can be folded independently without changing the result. Only the words that are
+        // not lowercased while being fed need to be folded upfront.
+        let mut ascii_start = 0;
+        let mut word_start = 0;
+        for word in input.split_inclusive(' ') {
+            if !is_ascii(word) {
+                self.feed_all(engine, &input.as_bytes()[ascii_start..word_start], out);
+                let folded = engine.uwu.locale.fold_case(word);
+                self.feed_all(engine, folded.as_bytes(), out);
+                ascii_start = word_start + word.len();
+            }
+            word_start += word.len();
+        }
+        self.feed_all(engine, &input.as_bytes()[ascii_start..], out);
+    }
+
+    /// Copies a protected token into the output as is, as if it was a word that no feature
+    /// changes.
+    fn push_verbatim(&mut self, engine: &UwuEngine, token: &str, out: &mut Vec<u8>) {
+        let last = token.chars().next_back();
+        let token = token.as_bytes();
+        if engine.uwu.expressions {
+            // No expression can match across the token
+            while let Some((pid, end)) = self.expressions_candidate.take() {
+                self.replace_expression(engine, pid, end, out);
+            }
+            self.resolve_deferred(engine, token[0], out);
+            self.flush_pending(engine, out);
+            self.expressions_sid = engine.expressions_start;
+            self.expressions_word = is_word_byte(token[token.len() - 1]);
+        }
+        if engine.uwu.nya {
+            self.flush_nya(engine, out);
+        }
+        // Like the stages of a pipeline, the nya step starts over after the token
+        self.nya_word = false;
+        if engine.uwu.stutter {
+            self.flush_stutter(engine, out);
+            self.stutter_after_space = last.is_some_and(is_stutter_boundary);
+            self.stutter_char = CharDecoder::default();
+        }
+
+        // Punctuation right before the token may still add an emoji, but never inside it
+        self.emit_emojis(engine, token[0], out);
+        for byte in &token[1..] {
+            self.push_output(*byte, out);
+        }
+        self.emojis_sid = engine.punctuation_start;
+        self.emojis_armed = false;
+    }
+
+    /// Uwuifies the bytes held back into the output.
+    ///
+    /// Unlike the start, the end of the input is not padded, so its whitespace is never confused
+    /// with padding. Instead, the features that look at the end of words run as if it was there.
+    pub(crate) fn finish(&mut self, engine: &UwuEngine, out: &mut Vec<u8>) {
+        self.start(engine, out);
+
+        if engine.uwu.expressions {
+            // The end of the input is the end of a word
+            if self.expressions_word {
+                self.expressions_word = false;
+                self.resolve_deferred(engine, b' ', out);
+                self.step_expressions(engine, BOUNDARY, None, out);
+            }
+            while let Some((pid, end)) = self.expressions_candidate.take() {
+                self.replace_expression(engine, pid, end, out);
+            }
+            if let Some(pid) = self.expressions_deferred.take() {
+                self.emit_expression(engine, pid, None, out);
+            }
+            self.flush_pending(engine, out);
+        }
+        if engine.uwu.nya {
+            self.flush_nya(engine, out);
+        }
+        if engine.uwu.stutter {
+            self.flush_stutter(engine, out);
+        }
+
+        // Punctuation at the end of the input is followed by whitespace
+        if engine.uwu.emojis && self.emojis_armed {
+            self.emojis_armed = false;
+            self.add_emoji(engine, true, out);
+        }
+    }
+
+    fn start(&mut self, engine: &UwuEngine, out: &mut Vec<u8>) {
+        if !self.started {
+            self.started = true;
+            // Pad input with a space so features that look at the start of words work on the
+            // first word
+            self.feed(engine, b' ', out);
+        }
+    }
+
+    /// Runs every step on the input, copying the bytes that leave the pipeline idle straight into
+    /// the output.
+    fn feed_all(&mut self, engine: &UwuEngine, input: &[u8], out: &mut Vec<u8>) {
+        let mut idx = 0;
+        while idx < input.len() {
+            if self.is_idle(engine) {
+                let passthrough = &engine.passthrough[self.expressions_word as usize];
+                let run = input[idx..]
+                    .iter()
+                    .position(|byte| !passthrough[*byte as usize])
+                    .unwrap_or(input.len() - idx);
+                if run > 0 {
+                    out.extend(
+                        input[idx..idx + run]
+                            .iter()
+                            .map(|byte| engine.passthrough_map[*byte as usize]),
+                    );
+                    idx += run;
+                    self.expressions_word = is_word_byte(input[idx - 1]);
+                    self.nya_word = self.expressions_word;
+                }
+                if idx == input.len() {
+                    break;
+                }
+            }
+
+            let byte = if engine.uwu.lowercase {
+                input[idx].to_ascii_lowercase()
+            } else {
+                input[idx]
+            };
+            self.feed(engine, byte, out);
+            idx += 1;
+        }
+    }
+
+    /// Whether no feature is in the middle of a match, so the bytes in the passthrough table can
+    /// be copied as is.
+    #[inline(always)]
+    fn is_idle(&self, engine: &UwuEngine) -> bool {
+        self.expressions_sid == engine.expressions_start
+            && self.expressions_deferred.is_none()
+            && !self.stutter_after_space
+            && self.stutter_char.remaining == 0
+            && self.nya_held.is_empty()
+            && self.nya_word == self.expressions_word
+            && self.stutter_held.is_empty()
+            && self.emojis_sid == engine.punctuation_start
+            && !self.skip_space
+    }
+
+    /// Runs the expressions step on a lowercase byte.
+    #[inline(always)]
+    fn feed(&mut self, engine: &UwuEngine, byte: u8, out: &mut Vec<u8>) {
+        let word = is_word_byte(byte);
+        if !engine.uwu.expressions {
+            // Still tracked for the passthrough table
+            self.expressions_word = word;
+            self.emit(engine, byte, out);
+            return;
+        }
+
+        // The start and end of words are searched as a boundary byte between the input bytes
+        if word != self.expressions_word {
+            self.expressions_word = word;
+            self.resolve_deferred(engine, byte, out);
+            self.step_expressions(engine, BOUNDARY, None, out);
+        }
+        self.resolve_deferred(engine, byte, out);
+        self.step_expressions(engine, byte, Some(byte), out);
+    }
+
+    /// Steps the expressions automaton, where `input` is the input byte to emit or hold back, if
+    /// any.
+    #[inline(always)]
+    fn step_expressions(
+        &mut self,
+        engine: &UwuEngine,
+        byte: u8,
+        input: Option<u8>,
+        out: &mut Vec<u8>,
+    ) {
+        let automaton = &engine.expressions;
+        self.expressions_sid = automaton.next_state(Anchored::No, self.expressions_sid, byte);
+
+        if self.expressions_sid == engine.expressions_start {
+            // Back at the start, so none of the pending bytes can be part of a match
+            self.flush_pending(engine, out);
+            if let Some(byte) = input {
+                self.emit(engine, byte, out);
+            }
+            return;
+        }
+
+        self.expressions_pending.extend(input);
+        if automaton.is_dead(self.expressions_sid) {
+            // With leftmost match semantics, no better match can be found
+            match self.expressions_candidate.take() {
+                Some((pid, end)) => self.replace_expression(engine, pid, end, out),
+                None => {
+                    self.expressions_sid = engine.expressions_start;
+                    self.flush_pending(engine, out);
+                }
+            }
+        } else if automaton.is_match(self.expressions_sid) {
+            let pid = automaton.match_pattern(self.expressions_sid, 0);
+            let end = self.expressions_pending.len();
+            if automaton.match_kind() != aho_corasick::MatchKind::Standard {
+                // A longer or preferred match may still follow
+                self.expressions_candidate = Some((pid, end));
+            } else {
+                self.replace_expression(engine, pid, end, out);
+            }
+        } else if self.expressions_candidate.is_none()
+            && self.expressions_pending.len() >= automaton.max_pattern_len()
+        {
+            // The oldest byte can no longer be part of a match
+            let byte = self.expressions_pending.remove(0);
+            self.emit(engine, byte, out);
+        }
+    }
+
+    /// Replaces the expression that ends at `end` in the pending bytes, and feeds the bytes that
+    /// follow it again from the start.
+    fn replace_expression(
+        &mut self,
+        engine: &UwuEngine,
+        pid: PatternID,
+        end: usize,
+        out: &mut Vec<u8>,
+    ) {
+        let start = end - engine.expressions_forms[pid.as_usize()].len;
+        self.expressions_sid = engine.expressions_start;
+
+        let mut pending = core::mem::take(&mut self.expressions_pending);
+        for byte in &pending[..start] {
+            self.emit(engine, *byte, out);
+        }
+        if engine.uwu.lowercase {
+            self.emit_expression(engine, pid, None, out);
+        } else {
+            // The case of single letter matches depends on the next byte
+            self.expressions_matched.clear();
+            self.expressions_matched
+                .extend_from_slice(&pending[start..end]);
+            self.expressions_deferred = Some(pid);
+        }
+        let rest = pending.split_off(end);
+        if !rest.is_empty() {
+            self.expressions_word = is_word_byte(pending[end - 1]);
+        }
+        pending.clear();
+        self.expressions_pending = pending;
+
+        for byte in rest {
+            self.feed(engine, byte, out);
+        }
+    }
+
+    /// Emits the expression whose case depends on the byte that follows it, if any.
+    #[inline(always)]
+    fn resolve_deferred(&mut self, engine: &UwuEngine, next: u8, out: &mut Vec<u8>) {
+        if let Some(pid) = self.expressions_deferred.take() {
+            self.emit_expression(engine, pid, Some(next), out);
+        }
+    }
+
+    fn flush_pending(&mut self, engine: &UwuEngine, out: &mut Vec<u8>) {
+        if self.expressions_pending.is_empty() {
+            return;
+        }
+        let mut pending = core::mem::take(&mut self.expressions_pending);
+        for byte in &pending {
+            self.emit(engine, *byte, out);
+        }
+        pending.clear();
+        self.expressions_pending = pending;
+    }
+
+    fn emit_expression(
+        &mut self,
+        engine: &UwuEngine,
+        pid: PatternID,
+        next: Option<u8>,
+        out: &mut Vec<u8>,
+    ) {
+        let replacement = engine.expressions_forms[pid.as_usize()]
+            .replacement
+            .as_str();
+        let case = if engine.uwu.lowercase {
+            Case::Lower
+        } else {
+            Case::detect(&self.expressions_matched, next.as_ref())
+        };
+        // Expressions are never nya'd, e.g. 'what' becomes 'nani', so the held bytes end before them
+        self.flush_nya(engine, out);
+        for byte in case.apply(replacement) {
+            self.emit_replaced(engine, byte, out);
+        }
+        self.nya_word = replacement.bytes().next_back().is_some_and(is_word_byte);
+    }
+
+    /// Runs the nya step on an input byte that the expressions step did not replace.
+    #[inline(always)]
+    fn emit(&mut self, engine: &UwuEngine, byte: u8, out: &mut Vec<u8>) {
+        let word = is_word_byte(byte);
+        let word_start = word && !self.nya_word;
+        self.nya_word = word;
+        if !engine.uwu.nya {
+            self.emit_replaced(engine, byte, out);
+            return;
+        }
+
+        if self.nya_exception {
+            if word {
+                self.nya_held.push(byte);
+                return;
+            }
+            self.flush_nya(engine, out);
+        } else if word_start && engine.nya_starts[byte as usize] {
+            // The whole word is held, since it may be an exception
+            self.nya_exception = true;
+            self.nya_held.push(byte);
+            return;
+        } else if let Some(n) = self.nya_held.pop() {
+            self.emit_replaced(engine, n, out);
+            if is_vowel(byte) {
+                self.emit_replaced(engine, nya_y(n, byte), out);
+            }
+        }
+        if matches!(byte, b'n' | b'N') {
+            self.nya_held.push(byte);
+            return;
+        }
+        self.emit_replaced(engine, byte, out);
+    }
+
+    /// Emits the held bytes of the nya step, adding a 'y' between every 'n' and vowel unless they
+    /// are an exception.
+    fn flush_nya(&mut self, engine: &UwuEngine, out: &mut Vec<u8>) {
+        if self.nya_held.is_empty() {
+            return;
+        }
+        let held = core::mem::take(&mut self.nya_held);
+        // The held bytes always end at a character boundary
+        let exception = core::mem::take(&mut self.nya_exception)
+            && core::str::from_utf8(&held)
+                .is_ok_and(|word| engine.nya_exceptions.contains(&word.to_lowercase()));
+        for (idx, byte) in held.iter().enumerate() {
+            self.emit_replaced(engine, *byte, out);
+            match held.get(idx + 1) {
+                Some(next) if !exception && matches!(byte, b'n' | b'N') && is_vowel(*next) => {
+                    self.emit_replaced(engine, nya_y(*byte, *next), out);
+                }
+                _ => {}
+            }
+        }
+        self.nya_held = held;
+        self.nya_held.clear();
+    }
+
+    /// Runs the w replace and stutter steps on a byte produced by the nya step.
+    #[inline(always)]
+    fn emit_replaced(&mut self, engine: &UwuEngine, byte: u8, out: &mut Vec<u8>) {
+        let byte = if engine.uwu.w_replace {
+            w_replace(byte)
+        } else {
+            byte
+        };
+
+        if engine.uwu.stutter {
+            if self.stutter_after_space || !self.stutter_held.is_empty() {
+                self.stutter_held.push(byte);
+                if let Some(char) = self.stutter_char.push(byte) {
+                    self.stutter(engine, char, out);
+                }
+                return;
+            }
+            if let Some(char) = self.stutter_char.push(byte) {
+                self.stutter_after_space = is_stutter_boundary(char);
+            }
+        }
+
+        self.emit_emojis(engine, byte, out);
+    }
+
+    /// Runs the stutter step on a character at the start of a word, which is the last one in the
+    /// held bytes.
+    fn stutter(&mut self, engine: &UwuEngine, char: char, out: &mut Vec<u8>) {
+        let rules = &engine.stutter;
+        if self.stutter_after_space {
+            // The first character of the word
+            self.stutter_after_space = false;
+            if char.is_alphabetic() {
+                if rules.needs_word() {
+                    // Hold the whole word, until the next stutter boundary
+                    return;
+                }
+                let mut buf = [0; 4];
+                let letter = char.encode_utf8(&mut buf);
+                let repeats = rules.draw(letter, &mut self.stutter_count, &mut self.rngs.stutter);
+                if repeats > 0 {
+                    self.stutter_repeats = repeats;
+                    self.stutter_grapheme = self.stutter_held.len();
+                    return;
+                }
+            }
+        } else if rules.needs_word() {
+            if !is_stutter_boundary(char) {
+                return;
+            }
+        } else if self.is_stutter_grapheme() {
+            // Marks, modifiers and Hangul jamo that extend the first character
+            self.stutter_grapheme = self.stutter_held.len();
+            return;
+        }
+        self.flush_stutter(engine, out);
+        self.stutter_after_space = is_stutter_boundary(char);
+    }
+
+    /// Whether the held bytes, which end at a character boundary, are a single grapheme cluster.
+    fn is_stutter_grapheme(&self) -> bool {
+        core::str::from_utf8(&self.stutter_held)
+            .is_ok_and(|held| first_grapheme(held).len() == held.len())
+    }
+
+    /// Emits the held bytes, repeating the start of the word if it stutters.
+    fn flush_stutter(&mut self, engine: &UwuEngine, out: &mut Vec<u8>) {
+        if self.stutter_held.is_empty() {
+            return;
+        }
+        let held = core::mem::take(&mut self.stutter_held);
+        let (prefix, repeats) = if engine.stutter.needs_word() {
+            // The held bytes start with the word, and always end at a character boundary
+            let text = core::str::from_utf8(&held).unwrap_or_default();
+            let word = tokenize(text).next().map_or("", |token| token.text);
+            let repeats =
+                engine
+                    .stutter
+                    .draw(word, &mut self.stutter_count, &mut self.rngs.stutter);
+            (engine.stutter.prefix(word).as_bytes(), repeats)
+        } else {
+            (&held[..self.stutter_grapheme], self.stutter_repeats)
+        };
+        for _ in 0..repeats {
+            for byte in prefix.iter().chain(b"-") {
+                self.emit_emojis(engine, *byte, out);
+            }
+        }
+        for byte in &held {
+            self.emit_emojis(engine, *byte, out);
+        }
+        self.stutter_held = held;
+        self.stutter_held.clear();
+        self.stutter_grapheme = 0;
+        self.stutter_repeats = 0;
+    }
+
+    /// Runs the emojis step on a byte produced by the stutter step.
+    #[inline(always)]
+    fn emit_emojis(&mut self, engine: &UwuEngine, byte: u8, out: &mut Vec<u8>) {
+        if !engine.uwu.emojis {
+            self.push_output(byte, out);
+        } else if self.emojis_armed {
+            self.emit_after_punctuation(engine, byte, out);
+        } else {
+            self.push_output(byte, out);
+            self.step_punctuation(engine, byte);
+        }
+    }
+
+    /// Runs the emojis step on a byte that follows punctuation, adding an emoji if it is
+    /// whitespace, or if the punctuation is standalone.
+    fn emit_after_punctuation(&mut self, engine: &UwuEngine, byte: u8, out: &mut Vec<u8>) {
+        if byte.is_ascii() && self.emojis_held.is_empty() {
+            self.resolve_punctuation(engine, byte as char, &[byte], out);
+            return;
+        }
+
+        // Whitespace may be a multi-byte character, so wait for the whole character
+        self.emojis_held.push(byte);
+        let next = core::str::from_utf8(&self.emojis_held)
+            .ok()
+            .and_then(|held| held.chars().next());
+        if let Some(next) = next {
+            let held = core::mem::take(&mut self.emojis_held);
+            self.resolve_punctuation(engine, next, &held, out);
+            self.emojis_held = held;
+            self.emojis_held.clear();
+        }
+    }
+
+    fn resolve_punctuation(
+        &mut self,
+        engine: &UwuEngine,
+        next: char,
+        bytes: &[u8],
+        out: &mut Vec<u8>,
+    ) {
+        self.emojis_armed = false;
+        if next == ' ' {
+            // The emoji goes after the space, and ends with its own space
+            self.push_output(b' ', out);
+            self.step_punctuation(engine, b' ');
+            self.add_emoji(engine, false, out);
+            return;
+        }
+
+        if next.is_whitespace() {
+            // The emoji goes before other whitespace, e.g. a line break
+            self.add_emoji(engine, true, out);
+        } else if self.emojis_standalone {
+            self.add_emoji(engine, false, out);
+        }
+        for byte in bytes {
+            self.emit_emojis(engine, *byte, out);
+        }
+    }
+
+    fn add_emoji(&mut self, engine: &UwuEngine, before_whitespace: bool, out: &mut Vec<u8>) {
+        if !engine.uwu.emojis_chance.draw(&mut self.rngs.emojis) {
+            return;
+        }
+        let emoji = engine.uwu.emoji_set.choose(&mut self.rngs.emojis);
+        if before_whitespace {
+            out.push(b' ');
+            self.markup.escape(emoji.trim_end(), out);
+        } else {
+            self.markup.escape(emoji, out);
+        }
+    }
+
+    #[inline(always)]
+    fn step_punctuation(&mut self, engine: &UwuEngine, byte: u8) {
+        let automaton = &engine.punctuation;
+        self.emojis_sid = automaton.next_state(Anchored::No, self.emojis_sid, byte);
+        if automaton.is_match(self.emojis_sid) {
+            self.emojis_armed = true;
+            self.emojis_standalone = (0..automaton.match_len(self.emojis_sid)).any(|idx| {
+                let pid = automaton.match_pattern(self.emojis_sid, idx);
+                engine.uwu.punctuation_set.is_standalone(pid.as_usize())
+            });
+        }
+    }
+
+    /// Pushes a byte into the output, dropping the leading padding.
+    #[inline(always)]
+    fn push_output(&mut self, byte: u8, out: &mut Vec<u8>) {
+        if self.skip_space {
+            self.skip_space = false;
+            if byte == b' ' {
+                return;
+            }
+        }
+        out.push(byte);
+    }
+}
+
+/// Whether a word that follows the character may stutter, e.g. whitespace or an opening bracket.
+pub(crate) const fn is_stutter_boundary(char: char) -> bool {
+    char.is_whitespace() || matches!(char, '(' | '[' | '{' | '"' | '¡' | '¿' | '«' | '‘' | '“')
+}
+
+/// Every character that is a stutter boundary, which are all below U+3001.
+pub(crate) const STUTTER_BOUNDARIES: [char; stutter_boundaries(&mut [])] = {
+    let mut boundaries = ['\0'; stutter_boundaries(&mut [])];
+    stutter_boundaries(&mut boundaries);
+    boundaries
+};
+
+/// Writes the stutter boundaries into the slice, if it is long enough, returning how many there
+/// are.
+const fn stutter_boundaries(out: &mut [char]) -> usize {
+    let mut count = 0;
+    let mut code = 0;
+    while code <= 0x3000 {
+        if let Some(char) = char::from_u32(code) {
+            if is_stutter_boundary(char) {
+                if count < out.len() {
+                    out[count] = char;
+                }
+                count += 1;
+            }
+        }
+        code += 1;
+    }
+    count
+}
+
+/// Decodes the characters emitted one byte at a time, to find the stutter boundaries and the
+/// first grapheme of words.
+#[derive(Default)]
+struct CharDecoder {
+    code_point: u32,
+    /// The number of bytes of the current character that were not emitted yet.
+    remaining: u32,
+}
+
+impl CharDecoder {
+    /// Returns the character completed by the byte, if any.
+    #[inline(always)]
+    fn push(&mut self, byte: u8) -> Option<char> {
+        if byte.is_ascii() {
+            self.remaining = 0;
+            return Some(byte as char);
+        }
+        if byte >= 0xc0 {
+            let len = byte.leading_ones();
+            self.remaining = len - 1;
+            self.code_point = u32::from(byte) & (0x7f >> len);
+            return None;
+        }
+        if self.remaining == 0 {
+            // The leading byte was copied as is, so the character is not a boundary nor the start
+            // of a word
+            return None;
+        }
+        self.code_point = (self.code_point << 6) | u32::from(byte & 0x3f);
+        self.remaining -= 1;
+        if self.remaining > 0 {
+            return None;
+        }
+        char::from_u32(self.code_point)
+    }
+}
+
+/// The markup language of the input, which decides how the text added by the pipeline is escaped.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Markup {
+    /// Plain text, where nothing needs to be escaped.
+    Plain,
+    /// Markdown, where characters that start inline syntax, e.g. '*', are escaped.
+    #[cfg(feature = "markdown")]
+    Markdown,
+    /// HTML, where characters that start markup, e.g. '<', are replaced with character references.
+    #[cfg(feature = "html")]
+    Html,
+}
+
+impl Markup {
+    fn escape(self, text: &str, out: &mut Vec<u8>) {
+        match self {
+            Markup::Plain => out.extend_from_slice(text.as_bytes()),
+            #[cfg(feature = "markdown")]
+            Markup::Markdown => crate::markdown::escape(text, out),
+            #[cfg(feature = "html")]
+            Markup::Html => crate::html::escape(text, out),
+        }
+    }
+}
+
+/// Whether the byte is a vowel that follows an 'n' in the nya feature.
+#[inline(always)]
+pub(crate) fn is_vowel(byte: u8) -> bool {
+    matches!(
+        byte,
+        b'a' | b'e' | b'i' | b'o' | b'u' | b'A' | b'E' | b'I' | b'O' | b'U'
+    )
+}
+
+/// The 'y' added between an 'n' and a vowel, which is uppercase only if both are, e.g. 'NYA' but
+/// 'Nya'.
+#[inline(always)]
+pub(crate) fn nya_y(n: u8, vowel: u8) -> u8 {
+    match n.is_ascii_uppercase() && vowel.is_ascii_uppercase() {
+        true => b'Y',
+        false => b'y',
+    }
+}
+
+/// Replaces 'l' and 'r' with 'w', preserving the case.
+#[inline(always)]
+pub(crate) fn w_replace(byte: u8) -> u8 {
+    match byte {
+        b'l' | b'r' => b'w',
+        b'L' | b'R' => b'W',
+        _ => byte,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aho_corasick::{AhoCorasickBuilder, Input};
+
+    use super::*;
+    use crate::stutter::StutterRules;
+    use crate::{
+        Chance, Dictionary, EmojiSet, Expression, Locale, MatchKind, Protection, PunctuationSet,
+        Seed, StutterMode, StutterOptions, Uwu, UwuPipelineBuilder,
+    };
+
+    /// A straightforward implementation, which runs each feature in a separate pass over the input.
+    fn uwuify_multi_pass(uwu: &Uwu, input: &str) -> String {
+        let mut rngs = FeatureRngs::new(uwu.seed.create_rng(input));
+        let input = if uwu.lowercase {
+            uwu.locale.fold_case(input)
+        } else {
+            input.to_owned()
+        };
+
+        let mut buf = input.into_bytes();
+        buf.insert(0, b' ');
+        let mut replaced = Vec::new();
+
+        if uwu.expressions {
+            // Insert a boundary byte wherever a word starts or ends, remembering the real index of
+            // every virtual byte
+            let mut virtual_buf = Vec::new();
+            let mut real_idx = Vec::new();
+            for (idx, byte) in buf.iter().enumerate() {
+                if idx > 0 && is_word_byte(buf[idx - 1]) != is_word_byte(*byte) {
+                    virtual_buf.push(BOUNDARY);
+                    real_idx.push(idx);
+                }
+                virtual_buf.push(*byte);
+                real_idx.push(idx);
+            }
+            if buf.last().is_some_and(|byte| is_word_byte(*byte)) {
+                virtual_buf.push(BOUNDARY);
+                real_idx.push(buf.len());
+            }
+            real_idx.push(buf.len());
+
+            let forms = uwu.dictionary.forms();
+            let matcher = AhoCorasickBuilder::new()
+                .ascii_case_insensitive(!uwu.lowercase)
+                .match_kind(uwu.dictionary.match_kind().into())
+                .build(forms.iter().map(|form| &form.pattern))
+                .unwrap();
+            let mut out = Vec::new();
+            let mut prev_idx = 0;
+            for mat in matcher.find_iter(Input::new(&virtual_buf)) {
+                let form = &forms[mat.pattern().as_usize()];
+                // A leading boundary belongs to the byte after it
+                let start = real_idx[mat.start()];
+                let end = start + form.len;
+                let case = if uwu.lowercase {
+                    Case::Lower
+                } else {
+                    Case::detect(&buf[start..end], buf.get(end))
+                };
+                out.extend_from_slice(&buf[prev_idx..start]);
+                let replaced_start = out.len();
+                out.extend(case.apply(&form.replacement));
+                replaced.push(replaced_start..out.len());
+                prev_idx = end;
+            }
+            out.extend_from_slice(&buf[prev_idx..]);
+            buf = out;
+        }
+        if uwu.nya {
+            let exceptions = uwu
+                .nya_exceptions
+                .iter()
+                .map(|word| word.to_lowercase())
+                .collect::<Vec<_>>();
+            // Expression replacements are copied as is, and the word that goes on after one is not
+            // a whole word, so it is never an exception
+            let mut pieces = Vec::new();
+            let mut copied = 0;
+            for range in replaced.iter().chain([&(buf.len()..buf.len())]) {
+                let after_word = copied > 0 && is_word_byte(buf[copied - 1]);
+                pieces.extend(
+                    buf[copied..range.start]
+                        .chunk_by(|a, b| is_word_byte(*a) == is_word_byte(*b))
+                        .enumerate()
+                        .map(|(idx, word)| (word, false, idx > 0 || !after_word)),
+                );
+                pieces.push((&buf[range.clone()], true, false));
+                copied = range.end;
+            }
+            let mut out = Vec::new();
+            for (word, replaced, whole) in pieces {
+                let lowercase = String::from_utf8(word.to_vec()).unwrap().to_lowercase();
+                let exception = whole && exceptions.contains(&lowercase);
+                for (idx, byte) in word.iter().enumerate() {
+                    out.push(*byte);
+                    if replaced || exception || !matches!(byte, b'n' | b'N') {
+                        continue;
+                    }
+                    match word.get(idx + 1) {
+                        Some(b'a' | b'e' | b'i' | b'o' | b'u') => out.push(b'y'),
+                        Some(b'A' | b'E' | b'I' | b'O' | b'U') => {
+                            out.push(if *byte == b'N' { b'Y' } else { b'y' })
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            buf = out;
+        }
+        if uwu.w_replace {
+            buf.iter_mut().for_each(|byte| match byte {
+                b'l' | b'r' => *byte = b'w',
+                b'L' | b'R' => *byte = b'W',
+                _ => {}
+            });
+        }
+        if uwu.stutter {
+            let mut out = String::new();
+            let rules = StutterRules::new(uwu);
+            let mut count = 0;
+            let mut after_boundary = false;
+            let text = String::from_utf8(buf).unwrap();
+            for (idx, char) in text.char_indices() {
+                if after_boundary {
+                    let word = tokenize(&text[idx..]).next().unwrap().text;
+                    for _ in 0..rules.draw(word, &mut count, &mut rngs.stutter) {
+                        out.push_str(rules.prefix(word));
+                        out.push('-');
+                    }
+                }
+                out.push(char);
+                after_boundary = is_stutter_boundary(char);
+            }
+            buf = out.into_bytes();
+        }
+        if uwu.emojis {
+            let text = String::from_utf8(buf).unwrap();
+            let mut out = String::new();
+            let mut armed = None;
+            for (idx, char) in text.char_indices() {
+                if let Some(standalone) = armed.take() {
+                    if (char.is_whitespace() || standalone)
+                        && uwu.emojis_chance.draw(&mut rngs.emojis)
+                    {
+                        let emoji = uwu.emoji_set.choose(&mut rngs.emojis);
+                        if char == ' ' {
+                            out.push(' ');
+                            out.push_str(emoji);
+                            continue;
+                        } else if char.is_whitespace() {
+                            out.push(' ');
+                            out.push_str(emoji.trim_end());
+                        } else {
+                            out.push_str(emoji);
+                        }
+                    }
+                }
+                out.push(char);
+
+                let prefix = &text[..idx + char.len_utf8()];
+                let mut matches = uwu
+                    .punctuation_set
+                    .iter()
+                    .filter(|punctuation| prefix.ends_with(&punctuation.text))
+                    .peekable();
+                if matches.peek().is_some() {
+                    armed = Some(matches.any(|punctuation| punctuation.standalone));
+                }
+            }
+            // The end of the input counts as whitespace
+            if armed.is_some() && uwu.emojis_chance.draw(&mut rngs.emojis) {
+                let emoji = uwu.emoji_set.choose(&mut rngs.emojis);
+                out.push(' ');
+                out.push_str(emoji.trim_end());
+            }
+            buf = out.into_bytes();
+        }
+
+        if buf.first() == Some(&b' ') {
+            buf.remove(0);
+        }
+        String::from_utf8(buf).unwrap()
+    }
+
+    //noinspection SpellCheckingInspection
+    #[test]
+    fn assert_single_pass_matches_multi_pass() {
+        const WORDS: [&str; 55] = [
+            "what",
+            "What",
+            "WHAT",
+            "cute",
+            "LOVE",
+            "Loved",
+            "loving",
+            "glove",
+            "small",
+            "Stupid",
+            "meow",
+            "fluff",
+            "nice",
+            "NICE",
+            "banana",
+            "Anime",
+            "nth",
+            "NoNe",
+            "N",
+            "n",
+            "Quick",
+            "question",
+            "Hello",
+            "hi",
+            "Hiya",
+            "hell",
+            "hellish",
+            "yay",
+            "yayay",
+            "ΣΑΣ",
+            "École",
+            "İstanbul",
+            "e\u{301}lan",
+            "привет",
+            "こんにちは",
+            "👋🏽",
+            "!",
+            ",",
+            ".",
+            "",
+            " ",
+            "\n",
+            "\t",
+            "\r\n",
+            "\u{3000}",
+            "?",
+            "…",
+            "~",
+            "。",
+            "、",
+            "(",
+            "[",
+            "\u{a0}",
+            "“",
+            "¿",
+        ];
+
+        let mut custom = Dictionary::new();
+        custom.insert("hiya", "hiya~");
+        custom.insert("hi", "hai");
+        custom.insert("hello", "henwo");
+        custom.insert_expression(Expression::new("hell", "heck").match_prefix());
+        custom.insert_expression(Expression::new("yay", "yay~").match_suffix());
+
+        let mut punctuation = PunctuationSet::default();
+        punctuation.insert("~");
+        punctuation.insert_standalone("、");
+
+        let mut rng = fastrand::Rng::with_seed(1);
+        for round in 0..500 {
+            let input = (0..rng.usize(0..30))
+                .map(|_| {
+                    let word = rng.choice(WORDS).unwrap();
+                    let separator = rng
+                        .choice(["", " ", ", ", ". ", "! ", "  ", "\n", "\t"])
+                        .unwrap();
+                    format!("{word}{separator}")
+                })
+                .collect::<String>();
+
+            let uwu = Uwu {
+                lowercase: rng.bool(),
+                locale: rng.choice([Locale::Root, Locale::Turkic]).unwrap(),
+                expressions: rng.bool(),
+                dictionary: if rng.bool() {
+                    let mut dictionary = custom.clone();
+                    dictionary.set_match_kind(
+                        rng.choice([MatchKind::LeftmostFirst, MatchKind::LeftmostLongest])
+                            .unwrap(),
+                    );
+                    dictionary
+                } else {
+                    Dictionary::default()
+                },
+                nya: rng.bool(),
+                nya_exceptions: if rng.bool() {
+                    vec!["Nice".to_owned(), "banana".to_owned()]
+                } else {
+                    Vec::new()
+                },
+                w_replace: rng.bool(),
+                stutter: rng.bool(),
+                stutter_chance: Chance::one_in(rng.u8(1..4)),
+                stutter_options: StutterOptions {
+                    min_length: rng.usize(1..4),
+                    stopwords: if rng.bool() {
+                        vec!["hi".to_owned(), "What".to_owned(), "cute".to_owned()]
+                    } else {
+                        Vec::new()
+                    },
+                    max_repeats: rng.u8(1..4),
+                    repeat_chance: Chance::one_in(rng.u8(1..3)),
+                    consonant_clusters: rng.bool(),
+                    mode: if rng.bool() {
+                        StutterMode::Random
+                    } else {
+                        StutterMode::EveryNth(rng.u32(1..4))
+                    },
+                },
+                emojis: rng.bool(),
+                emojis_chance: Chance::one_in(rng.u8(1..4)),
+                emoji_set: EmojiSet::default(),
+                punctuation_set: if rng.bool() {
+                    punctuation.clone()
+                } else {
+                    PunctuationSet::default()
+                },
+                protection: Protection::NONE,
+                protected_words: Vec::new(),
+                seed: Seed::Fixed(round),
+            };
+            let expected = uwuify_multi_pass(&uwu, &input);
+            let engine = uwu.compile().unwrap();
+            assert_eq!(
+                engine.uwuify(&input).unwrap(),
+                expected,
+                "input: {input:?}, uwu: {uwu:?}"
+            );
+
+            // The built-in stages also match when they run as separate transforms
+            let stages = UwuPipelineBuilder::new(engine).build().unwrap();
+            assert_eq!(
+                stages.run_stages(&input, &[]).unwrap(),
+                expected,
+                "input: {input:?}, uwu: {uwu:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,512 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use aho_corasick::AhoCorasick;
+
+use crate::dictionary::is_word_byte;
+use crate::UwuError;
+
+/// Specifies which kinds of tokens are passed through verbatim, untouched by every feature.
+///
+/// Example:
+/// ```
+/// use uwu_rs::Uwu;
+///
+/// let uwu = Uwu::builder().w_replace().protect_urls().protect_mentions().build();
+/// assert_eq!(
+///     uwu.uwuify("hello @carol, see https://example.org/rust").unwrap(),
+///     "hewwo @carol, see https://example.org/rust"
+/// );
+/// ```
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct Protection {
+    /// Protects URLs with a scheme or starting with 'www.', e.g. `https://example.org/rust`.
+    pub urls: bool,
+    /// Protects email addresses, e.g. 'carol@example.org'.
+    pub emails: bool,
+    /// Protects mentions, e.g. '@carol', '@carol@example.org' or '<@123>'.
+    pub mentions: bool,
+    /// Protects hashtags, e.g. '#rustlang'.
+    pub hashtags: bool,
+    /// Protects emoji shortcodes, e.g. ':thumbs_up:'.
+    pub shortcodes: bool,
+    /// Protects channel references, e.g. '<#123>' or '<#C024BE7LR|general>'.
+    pub channels: bool,
+}
+
+impl Protection {
+    /// Protects nothing.
+    pub const NONE: Self = Self {
+        urls: false,
+        emails: false,
+        mentions: false,
+        hashtags: false,
+        shortcodes: false,
+        channels: false,
+    };
+
+    /// Protects every kind of token.
+    pub const ALL: Self = Self {
+        urls: true,
+        emails: true,
+        mentions: true,
+        hashtags: true,
+        shortcodes: true,
+        channels: true,
+    };
+
+    /// Whether no kind of token is protected.
+    pub fn is_none(&self) -> bool {
+        *self == Self::NONE
+    }
+
+    /// Finds the ranges of the protected tokens in the input, in order.
+    pub(crate) fn find(&self, input: &str) -> Vec<Range<usize>> {
+        let bytes = input.as_bytes();
+        let mut ranges = Vec::new();
+        let mut idx = 0;
+        while idx < bytes.len() {
+            // Tokens never start in the middle of a word
+            let starts_word = idx == 0 || !is_token_byte(bytes[idx - 1]);
+            match starts_word.then(|| self.token_len(&bytes[idx..])).flatten() {
+                Some(len) => {
+                    ranges.push(idx..idx + len);
+                    idx += len;
+                }
+                None => idx += 1,
+            }
+        }
+        ranges
+    }
+
+    /// The length of the protected token at the start of the input, if any.
+    fn token_len(&self, input: &[u8]) -> Option<usize> {
+        match input[0] {
+            b'<' if self.channels && input.starts_with(b"<#") => bracketed(input),
+            b'<' if self.mentions && input.starts_with(b"<@") => bracketed(input),
+            b'@' if self.mentions => mention(input),
+            b'#' if self.hashtags => hashtag(input),
+            b':' if self.shortcodes => shortcode(input),
+            byte if byte.is_ascii_alphanumeric() => self
+                .urls
+                .then(|| url(input))
+                .flatten()
+                .or_else(|| self.emails.then(|| email(input)).flatten()),
+            _ => None,
+        }
+    }
+}
+
+/// A span of the input that is passed through verbatim, e.g. a username known by the caller.
+///
+/// Example:
+/// ```
+/// use uwu_rs::{ProtectedSpan, Uwu};
+///
+/// let uwu = Uwu::builder().w_replace().build();
+/// let uwuified = uwu
+///     .uwuify_protecting("réally, ask Laura", &[ProtectedSpan::Chars(12..17)])
+///     .unwrap();
+/// assert_eq!(uwuified, "wéawwy, ask Laura");
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ProtectedSpan {
+    /// A range of bytes, which must start and end on character boundaries.
+    Bytes(Range<usize>),
+    /// A range of characters, i.e. Unicode scalar values.
+    Chars(Range<usize>),
+}
+
+impl ProtectedSpan {
+    /// Converts the span into a range of bytes of the input.
+    fn to_bytes(&self, input: &str) -> Result<Range<usize>, UwuError> {
+        let range = match self {
+            ProtectedSpan::Bytes(range) => range.clone(),
+            ProtectedSpan::Chars(range) => {
+                let mut offsets = input
+                    .char_indices()
+                    .map(|(idx, _)| idx)
+                    .chain([input.len()]);
+                let start = offsets.nth(range.start);
+                let end = match range.end.checked_sub(range.start) {
+                    Some(0) => start,
+                    Some(len) => offsets.nth(len - 1),
+                    None => None,
+                };
+                match (start, end) {
+                    (Some(start), Some(end)) => start..end,
+                    _ => usize::MAX..usize::MAX,
+                }
+            }
+        };
+        let valid = range.start <= range.end
+            && input.is_char_boundary(range.start)
+            && input.is_char_boundary(range.end);
+        if valid {
+            Ok(range)
+        } else {
+            Err(UwuError::InvalidProtection(format!(
+                "{self:?} is not a valid span of the input"
+            )))
+        }
+    }
+}
+
+/// Checks that every protected word can be matched, i.e. it is not empty and does not contain
+/// whitespace.
+pub(crate) fn validate_words(words: &[String]) -> Result<(), UwuError> {
+    for word in words {
+        if word.is_empty() || word.contains(char::is_whitespace) {
+            return Err(UwuError::InvalidProtection(format!(
+                "protected words must not be empty or contain whitespace: {word:?}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Finds the ranges of the input that are passed through verbatim, i.e. the protected tokens, the
+/// protected words and the spans given by the caller, sorted and without overlaps.
+pub(crate) fn protected_ranges(
+    protection: &Protection,
+    words: Option<&AhoCorasick>,
+    input: &str,
+    spans: &[ProtectedSpan],
+) -> Result<Vec<Range<usize>>, UwuError> {
+    let mut ranges = protection.find(input);
+    if let Some(words) = words {
+        let bytes = input.as_bytes();
+        // Words only match whole words, e.g. 'Rust' does not match 'Rustacean'
+        ranges.extend(
+            words
+                .find_iter(input)
+                .map(|mat| mat.range())
+                .filter(|range| {
+                    let starts_word = range.start == 0 || !is_word_byte(bytes[range.start - 1]);
+                    let ends_word = bytes.get(range.end).is_none_or(|byte| !is_word_byte(*byte));
+                    starts_word && ends_word
+                }),
+        );
+    }
+    for span in spans {
+        ranges.push(span.to_bytes(input)?);
+    }
+
+    ranges.retain(|range| !range.is_empty());
+    ranges.sort_unstable_by_key(|range| range.start);
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    Ok(merged)
+}
+
+/// Whether the byte can be part of a token, so a token can not start right after it.
+fn is_token_byte(byte: u8) -> bool {
+    is_word_byte(byte) || matches!(byte, b'_' | b'@' | b'#' | b'.' | b'-' | b'+' | b'/' | b'%')
+}
+
+/// Matches a URL, e.g. 'https://example.org/rust' or 'www.example.org', without trailing
+/// punctuation.
+fn url(input: &[u8]) -> Option<usize> {
+    let scheme = input
+        .iter()
+        .position(|byte| !byte.is_ascii_alphanumeric() && !matches!(byte, b'+' | b'.' | b'-'))
+        .filter(|len| input[*len..].starts_with(b"://"))
+        .map(|len| len + 3);
+    let start = match scheme {
+        Some(len) => len,
+        None if input.len() > 4 && input[..4].eq_ignore_ascii_case(b"www.") => 4,
+        None => return None,
+    };
+
+    let mut len = start
+        + run(&input[start..], |byte| {
+            !byte.is_ascii_whitespace() && byte != b'<'
+        });
+    // Trailing punctuation usually belongs to the sentence, unless it closes a bracket of the URL
+    while len > start {
+        let last = input[len - 1];
+        let unbalanced = |open, close| {
+            last == close
+                && input[..len].iter().filter(|byte| **byte == open).count()
+                    < input[..len].iter().filter(|byte| **byte == close).count()
+        };
+        if b".,;:!?'\"*".contains(&last) || unbalanced(b'(', b')') || unbalanced(b'[', b']') {
+            len -= 1;
+        } else {
+            break;
+        }
+    }
+    (len > start).then_some(len)
+}
+
+/// Matches an email address, e.g. 'carol@example.org'.
+fn email(input: &[u8]) -> Option<usize> {
+    let local = run(input, |byte| {
+        byte.is_ascii_alphanumeric() || matches!(byte, b'.' | b'_' | b'%' | b'+' | b'-')
+    });
+    if input.get(local) != Some(&b'@') {
+        return None;
+    }
+    domain(&input[local + 1..]).map(|len| local + 1 + len)
+}
+
+/// Matches a mention, e.g. '@carol' or '@carol@example.org'.
+fn mention(input: &[u8]) -> Option<usize> {
+    let mut len = 1 + name(&input[1..])?;
+    if input.get(len) == Some(&b'@') {
+        if let Some(domain) = domain(&input[len + 1..]) {
+            len += 1 + domain;
+        }
+    }
+    Some(len)
+}
+
+/// Matches a hashtag, e.g. '#rustlang'.
+fn hashtag(input: &[u8]) -> Option<usize> {
+    let len = run(&input[1..], |byte| is_word_byte(byte) || byte == b'_');
+    (len > 0).then_some(1 + len)
+}
+
+/// Matches an emoji shortcode, e.g. ':thumbs_up:' or ':+1:'.
+fn shortcode(input: &[u8]) -> Option<usize> {
+    let len = run(&input[1..], |byte| {
+        byte.is_ascii_alphanumeric() || matches!(byte, b'_' | b'+' | b'-')
+    });
+    let end = 1 + len + 1;
+    let closed = len > 0 && input.get(end - 1) == Some(&b':');
+    let ends_word = input.get(end).is_none_or(|byte| !is_word_byte(*byte));
+    (closed && ends_word).then_some(end)
+}
+
+/// Matches a reference between angle brackets, e.g. '<#123>' or '<@U024BE7LR|carol>'.
+fn bracketed(input: &[u8]) -> Option<usize> {
+    let len = run(&input[2..], |byte| {
+        !byte.is_ascii_whitespace() && !matches!(byte, b'<' | b'>')
+    });
+    (len > 0 && input.get(2 + len) == Some(&b'>')).then_some(2 + len + 1)
+}
+
+/// Matches a name of letters, digits and underscores, which can be separated by single dots or
+/// hyphens, e.g. 'carol.smith'.
+fn name(input: &[u8]) -> Option<usize> {
+    let is_name_byte = |byte: u8| byte.is_ascii_alphanumeric() || byte == b'_';
+    let mut len = run(input, is_name_byte);
+    if len == 0 {
+        return None;
+    }
+    while matches!(input.get(len), Some(b'.' | b'-')) {
+        let part = run(&input[len + 1..], is_name_byte);
+        if part == 0 {
+            break;
+        }
+        len += 1 + part;
+    }
+    Some(len)
+}
+
+/// Matches a domain name with at least two labels, e.g. 'example.org'.
+fn domain(input: &[u8]) -> Option<usize> {
+    let is_label_byte = |byte: u8| byte.is_ascii_alphanumeric() || byte == b'-';
+    let mut len = run(input, is_label_byte);
+    let mut labels = 1;
+    if len == 0 {
+        return None;
+    }
+    while input.get(len) == Some(&b'.') {
+        let label = run(&input[len + 1..], is_label_byte);
+        if label == 0 {
+            break;
+        }
+        len += 1 + label;
+        labels += 1;
+    }
+    (labels > 1).then_some(len)
+}
+
+/// The length of the run of bytes at the start of the input that match the predicate.
+fn run(input: &[u8], predicate: impl Fn(u8) -> bool) -> usize {
+    input
+        .iter()
+        .position(|byte| !predicate(*byte))
+        .unwrap_or(input.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Uwu;
+
+    #[test]
+    fn assert_find_tokens() {
+        fn find(protection: Protection, input: &str) -> Vec<&str> {
+            protection
+                .find(input)
+                .into_iter()
+                .map(|range| &input[range])
+                .collect()
+        }
+
+        assert_eq!(
+            find(
+                Protection::ALL,
+                "see (https://en.wikipedia.org/wiki/Rust_(language)), www.rust-lang.org."
+            ),
+            [
+                "https://en.wikipedia.org/wiki/Rust_(language)",
+                "www.rust-lang.org"
+            ]
+        );
+        assert_eq!(
+            find(
+                Protection::ALL,
+                "mail carol@example.org. or @carol@mastodon.social!"
+            ),
+            ["carol@example.org", "@carol@mastodon.social"]
+        );
+        assert_eq!(
+            find(
+                Protection::ALL,
+                "@carol.smith: #rustlang #1 in <#C024BE7LR|general> <@123>"
+            ),
+            [
+                "@carol.smith",
+                "#rustlang",
+                "#1",
+                "<#C024BE7LR|general>",
+                "<@123>"
+            ]
+        );
+        assert_eq!(
+            find(Protection::ALL, ":thumbs_up: :+1:! 12:30:00 a:b: :no"),
+            [":thumbs_up:", ":+1:"]
+        );
+        assert_eq!(
+            find(Protection::ALL, "issue#1 word@ e.g. a@b x://"),
+            Vec::<&str>::new()
+        );
+
+        let protection = Protection {
+            hashtags: true,
+            ..Protection::NONE
+        };
+        assert_eq!(find(protection, "#rust @carol https://rust.rs"), ["#rust"]);
+        assert!(Protection::NONE.find("#rust @carol").is_empty());
+    }
+
+    //noinspection SpellCheckingInspection
+    #[test]
+    fn assert_protected_tokens() {
+        let uwu = Uwu::builder()
+            .expressions()
+            .w_replace()
+            .stutter(1)
+            .emojis(1)
+            .clear_emojis()
+            .add_emoji("OwO ", 1)
+            .protect_urls()
+            .protect_emails()
+            .protect_mentions()
+            .protect_hashtags()
+            .protect_shortcodes()
+            .protect_channels()
+            .build();
+        assert_eq!(
+            uwu.uwuify("Hello @carol, read https://example.org/rust. #rustlang :heart: <#42>")
+                .unwrap(),
+            "H-Hewwo @carol, OwO w-wead https://example.org/rust. OwO #rustlang :heart: <#42>"
+        );
+        assert_eq!(
+            uwu.uwuify("what RULES:small@cute.org").unwrap(),
+            "n-nani W-WUWES:small@cute.org"
+        );
+
+        let uwu = Uwu::builder().lowercase().protect_urls().build();
+        assert_eq!(
+            uwu.uwuify("VISIT HTTPS://EXAMPLE.ORG/Rust").unwrap(),
+            "visit HTTPS://EXAMPLE.ORG/Rust"
+        );
+
+        let uwu = Uwu::builder().w_replace().protect_mentions().build();
+        assert_eq!(uwu.uwuify("@carol #rustlang").unwrap(), "@carol #wustwang");
+
+        // Protection is opt-in, with the same default for the builder and `Uwu::new`
+        assert_eq!(Uwu::new().protection, Protection::NONE);
+        assert_eq!(Uwu::builder().build().protection, Protection::NONE);
+    }
+
+    //noinspection SpellCheckingInspection
+    #[test]
+    fn assert_protected_words() {
+        let uwu = Uwu::builder()
+            .lowercase()
+            .expressions()
+            .w_replace()
+            .stutter(1)
+            .protect_word("Rust")
+            .protect_word("Ferris")
+            .protect_word("love")
+            .build();
+        assert_eq!(
+            uwu.uwuify("Rust and Ferris, Rustaceans love RUST").unwrap(),
+            "Rust a-and Ferris, w-wustaceans love w-wust"
+        );
+
+        let mut builder = Uwu::builder();
+        builder.protect_word("Ferris the crab");
+        assert!(matches!(
+            builder.build_engine(),
+            Err(UwuError::InvalidProtection(..))
+        ));
+    }
+
+    //noinspection SpellCheckingInspection
+    #[test]
+    fn assert_protected_spans() {
+        let uwu = Uwu::builder()
+            .lowercase()
+            .w_replace()
+            .emojis(1)
+            .clear_emojis()
+            .add_emoji("OwO ", 1)
+            .build();
+        let spans = [
+            ProtectedSpan::Bytes(6..11),
+            ProtectedSpan::Chars(17..29),
+            ProtectedSpan::Bytes(18..21),
+        ];
+        assert_eq!(
+            uwu.uwuify_protecting("Hello Laura, run `let l = r;` now.", &spans)
+                .unwrap(),
+            "hewwo Laura, OwO wun `let l = r;` now. OwO"
+        );
+        assert_eq!(
+            uwu.uwuify_protecting("Réal", &[ProtectedSpan::Chars(0..2)])
+                .unwrap(),
+            "Réaw"
+        );
+
+        for span in [
+            ProtectedSpan::Bytes(1..2),
+            ProtectedSpan::Bytes(3..9),
+            ProtectedSpan::Chars(2..9),
+            ProtectedSpan::Chars(Range { start: 3, end: 2 }),
+        ] {
+            assert!(matches!(
+                uwu.uwuify_protecting("Réal", &[span]),
+                Err(UwuError::InvalidProtection(..))
+            ));
+        }
+    }
+}
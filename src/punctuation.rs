@@ -0,0 +1,235 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::{dict, UwuError};
+
+/// A punctuation mark after which the emojis feature adds emojis, e.g. '!'.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Punctuation {
+    /// The punctuation mark, e.g. '!'. It must not contain whitespace.
+    pub text: String,
+    /// Whether emojis are added even when the punctuation is not followed by whitespace, e.g. for
+    /// full-width punctuation like '。', since CJK text does not separate sentences with spaces.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub standalone: bool,
+}
+
+/// The punctuation marks after which the emojis feature adds emojis.
+///
+/// Emojis are added when a punctuation mark is followed by whitespace or the end of the input.
+/// After a space, the emoji is added after the space, e.g. 'hi, there' becomes 'hi, OwO there'.
+/// Before other whitespace, the emoji is added before it, e.g. 'hi.\n' becomes 'hi. OwO\n'.
+///
+/// The default set contains ',', '.', '!', '?' and '…', which also covers '...' and '?!', plus the
+/// standalone full-width punctuation '。', '！' and '？'.
+///
+/// Example:
+/// ```
+/// use uwu_rs::Uwu;
+///
+/// let uwu = Uwu::builder().emojis(1).add_punctuation(";").build();
+/// let uwuified = uwu.uwuify("Hello; world").unwrap();
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "PunctuationSetFile", into = "PunctuationSetFile")
+)]
+pub struct PunctuationSet {
+    punctuation: Vec<Punctuation>,
+}
+
+impl Default for PunctuationSet {
+    fn default() -> Self {
+        let mut punctuation_set = Self::empty();
+        for text in dict::PUNCTUATION {
+            punctuation_set.insert(text);
+        }
+        for text in dict::PUNCTUATION_STANDALONE {
+            punctuation_set.insert_standalone(text);
+        }
+        punctuation_set
+    }
+}
+
+impl PunctuationSet {
+    /// Create a new set with the default punctuation marks.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new set without any punctuation marks.
+    pub fn empty() -> Self {
+        Self {
+            punctuation: Vec::new(),
+        }
+    }
+
+    /// Adds a punctuation mark that adds emojis when followed by whitespace or the end of the
+    /// input, e.g. ';'.
+    pub fn insert<T: Into<String>>(&mut self, text: T) {
+        self.insert_punctuation(text.into(), false);
+    }
+
+    /// Adds a punctuation mark that adds emojis even when not followed by whitespace, e.g. '。'.
+    pub fn insert_standalone<T: Into<String>>(&mut self, text: T) {
+        self.insert_punctuation(text.into(), true);
+    }
+
+    /// Removes a punctuation mark. Returns whether it was in the set.
+    pub fn remove<T: AsRef<str>>(&mut self, text: T) -> bool {
+        let len = self.punctuation.len();
+        self.punctuation
+            .retain(|punctuation| punctuation.text != text.as_ref());
+        self.punctuation.len() != len
+    }
+
+    /// Whether the punctuation mark is in the set.
+    pub fn contains<T: AsRef<str>>(&self, text: T) -> bool {
+        self.position(text.as_ref()).is_some()
+    }
+
+    /// Removes every punctuation mark.
+    pub fn clear(&mut self) {
+        self.punctuation.clear();
+    }
+
+    /// Iterates over the punctuation marks, in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = &Punctuation> {
+        self.punctuation.iter()
+    }
+
+    /// The number of punctuation marks.
+    pub fn len(&self) -> usize {
+        self.punctuation.len()
+    }
+
+    /// Whether there are no punctuation marks.
+    pub fn is_empty(&self) -> bool {
+        self.punctuation.is_empty()
+    }
+
+    /// Checks that every punctuation mark can be matched, i.e. it is not empty and does not contain
+    /// whitespace.
+    pub fn validate(&self) -> Result<(), UwuError> {
+        for punctuation in &self.punctuation {
+            if punctuation.text.is_empty() || punctuation.text.contains(char::is_whitespace) {
+                return Err(UwuError::InvalidPunctuation(format!(
+                    "punctuation must not be empty or contain whitespace: {:?}",
+                    punctuation.text
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the punctuation mark at the given index, in the order they were added, is
+    /// standalone.
+    pub(crate) fn is_standalone(&self, idx: usize) -> bool {
+        self.punctuation[idx].standalone
+    }
+
+    fn insert_punctuation(&mut self, text: String, standalone: bool) {
+        match self.position(&text) {
+            Some(idx) => self.punctuation[idx].standalone = standalone,
+            None => self.punctuation.push(Punctuation { text, standalone }),
+        }
+    }
+
+    fn position(&self, text: &str) -> Option<usize> {
+        self.punctuation
+            .iter()
+            .position(|punctuation| punctuation.text == text)
+    }
+}
+
+impl<'a> IntoIterator for &'a PunctuationSet {
+    type Item = &'a Punctuation;
+    type IntoIter = core::slice::Iter<'a, Punctuation>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.punctuation.iter()
+    }
+}
+
+/// The serialized format of punctuation sets.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct PunctuationSetFile {
+    #[serde(default)]
+    punctuation: Vec<Punctuation>,
+}
+
+#[cfg(feature = "serde")]
+impl From<PunctuationSet> for PunctuationSetFile {
+    fn from(punctuation_set: PunctuationSet) -> Self {
+        Self {
+            punctuation: punctuation_set.punctuation,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<PunctuationSetFile> for PunctuationSet {
+    fn from(file: PunctuationSetFile) -> Self {
+        let mut punctuation_set = Self::empty();
+        for punctuation in file.punctuation {
+            punctuation_set.insert_punctuation(punctuation.text, punctuation.standalone);
+        }
+        punctuation_set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Uwu;
+
+    #[test]
+    fn assert_punctuation_set() {
+        let mut punctuation_set = PunctuationSet::new();
+        assert!(punctuation_set.contains("?"));
+        assert!(punctuation_set.validate().is_ok());
+
+        assert!(punctuation_set.remove("?"));
+        assert!(!punctuation_set.remove("?"));
+        assert!(!punctuation_set.contains("?"));
+
+        punctuation_set.insert("a b");
+        assert!(punctuation_set.validate().is_err());
+        punctuation_set.clear();
+        punctuation_set.insert("");
+        assert!(punctuation_set.validate().is_err());
+    }
+
+    //noinspection SpellCheckingInspection
+    #[test]
+    fn assert_punctuation_triggers() {
+        fn convert(input: &str) -> String {
+            Uwu::builder()
+                .emojis(1)
+                .clear_emojis()
+                .add_emoji("OwO ", 1)
+                .add_punctuation("~")
+                .build()
+                .uwuify(input)
+                .unwrap()
+        }
+
+        assert_eq!(convert("what? ok"), "what? OwO ok");
+        assert_eq!(convert("wait... ok?!"), "wait... OwO ok?! OwO");
+        assert_eq!(convert("so… yes"), "so… OwO yes");
+        assert_eq!(convert("hi~"), "hi~ OwO");
+        assert_eq!(
+            convert("end.\nnext line.\r\nlast"),
+            "end. OwO\nnext line. OwO\r\nlast"
+        );
+        assert_eq!(convert("tab.\tok"), "tab. OwO\tok");
+        assert_eq!(convert("wide.\u{3000}ok"), "wide. OwO\u{3000}ok");
+        assert_eq!(convert("3.14 e.g"), "3.14 e.g");
+        assert_eq!(convert("你好。我很好！"), "你好。OwO 我很好！ OwO");
+    }
+}
@@ -0,0 +1,119 @@
+/// The default seed, used so the same input always produces the same output.
+pub const DEFAULT_SEED: u64 = 75777521; // 'uwu!' = 75 77 75 21
+
+/// Specifies how the random number generator used by the stutter and emojis features is seeded.
+///
+/// Example:
+/// ```
+/// use uwu_rs::{Seed, Uwu};
+///
+/// let uwu = Uwu::builder().stutter(2).seed(Seed::Fixed(42)).build();
+/// assert_eq!(uwu.uwuify("hello world").unwrap(), uwu.uwuify("hello world").unwrap());
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum Seed {
+    /// Uses the same seed on every call, so the same input always produces the same output.
+    Fixed(u64),
+    /// Derives the seed from a hash of the input, so different inputs produce different patterns
+    /// while the same input still produces the same output.
+    ///
+    /// Streams do not know the whole input upfront, so they hash the first chunk they read instead,
+    /// and their random features differ from uwuifying the whole input at once.
+    Input,
+    /// Seeds from a source of entropy provided by the OS, so every call produces a different output.
+    ///
+    /// Without the `std` feature there is no OS entropy, so the seed is derived from a global
+    /// counter and the address of the stack instead. Every call still produces a different output,
+    /// but the sequence of outputs may repeat between runs.
+    Random,
+}
+
+impl Default for Seed {
+    fn default() -> Self {
+        Self::Fixed(DEFAULT_SEED)
+    }
+}
+
+impl Seed {
+    /// Creates a new random number generator for the given input.
+    pub fn create_rng(&self, input: &str) -> fastrand::Rng {
+        let seed = match self {
+            Seed::Fixed(seed) => *seed,
+            Seed::Input => hash(input.as_bytes()),
+            Seed::Random => random_seed(),
+        };
+        fastrand::Rng::with_seed(seed)
+    }
+}
+
+/// The random number generators used by each feature while uwuifying an input.
+#[derive(Clone, Debug)]
+pub(crate) struct FeatureRngs {
+    pub(crate) stutter: fastrand::Rng,
+    pub(crate) emojis: fastrand::Rng,
+}
+
+impl FeatureRngs {
+    /// Gives each feature its own copy of the generator, so enabling one feature does not change
+    /// the output of the others.
+    pub(crate) fn new(rng: fastrand::Rng) -> Self {
+        Self {
+            stutter: rng.clone(),
+            emojis: rng,
+        }
+    }
+
+    /// Derives independent generators for each feature from a caller-owned generator, advancing
+    /// it in the process.
+    pub(crate) fn fork(rng: &mut fastrand::Rng) -> Self {
+        Self {
+            stutter: fastrand::Rng::with_seed(rng.u64(..)),
+            emojis: fastrand::Rng::with_seed(rng.u64(..)),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+fn random_seed() -> u64 {
+    use std::hash::{BuildHasher, Hasher};
+
+    std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish()
+}
+
+#[cfg(not(feature = "std"))]
+fn random_seed() -> u64 {
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    static COUNTER: AtomicU32 = AtomicU32::new(0);
+    #[cfg(target_has_atomic = "32")]
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    // Targets without compare-and-swap, e.g. `thumbv6m-none-eabi`, only load and store atomically,
+    // so seeds drawn concurrently may share a count and differ only by their stack address
+    #[cfg(not(target_has_atomic = "32"))]
+    let count = {
+        let count = COUNTER.load(Ordering::Relaxed);
+        COUNTER.store(count.wrapping_add(1), Ordering::Relaxed);
+        count
+    };
+    let local = count;
+    let address = &local as *const u32 as usize;
+    hash(&((u64::from(count) << 32) ^ address as u64).to_le_bytes())
+}
+
+/// Hashes the input with FNV-1a. A fixed algorithm is used instead of the standard library hasher
+/// so the seeds remain stable across Rust versions.
+fn hash(input: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    input.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(PRIME)
+    })
+}
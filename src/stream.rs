@@ -0,0 +1,355 @@
+use std::io::{Read, Write};
+
+use crate::dictionary::is_word_byte;
+use crate::pipeline::Pipeline;
+use crate::seed::FeatureRngs;
+use crate::{UwuEngine, UwuError};
+
+/// The size of the chunks read from the underlying reader.
+const CHUNK_SIZE: usize = 8 * 1024;
+/// The maximum length of the text buffered without whitespace, e.g. in minified JSON.
+const MAX_SEGMENT: usize = 64 * 1024;
+
+/// Splits a stream of bytes into segments that are fed to the uwu pipeline.
+///
+/// A segment usually ends right after ASCII whitespace, so segments are always valid UTF-8 on
+/// their own and can be lowercased independently. Runs of text without whitespace longer than
+/// [`MAX_SEGMENT`] are cut after their last ASCII punctuation, or else at their last character
+/// boundary, so the memory used stays bounded. Such cuts may split a protected token or change the
+/// case of a final sigma, the only ways in which the output may differ from the in-memory call.
+struct Segmenter {
+    engine: UwuEngine,
+    pipeline: Option<Pipeline>,
+    pending: Vec<u8>,
+}
+
+impl Segmenter {
+    fn new(engine: UwuEngine) -> Self {
+        Self {
+            engine,
+            pipeline: None,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Buffers the data and uwuifies every complete segment into the output.
+    fn push(&mut self, data: &[u8], output: &mut Vec<u8>) -> Result<(), UwuError> {
+        self.pending.extend_from_slice(data);
+        loop {
+            let end = match self.pending.iter().rposition(u8::is_ascii_whitespace) {
+                Some(idx) => idx + 1,
+                None if self.pending.len() > MAX_SEGMENT => cut(&self.pending),
+                None => return Ok(()),
+            };
+            let mut pending = core::mem::take(&mut self.pending);
+            self.process(&pending[..end], output)?;
+            pending.drain(..end);
+            self.pending = pending;
+        }
+    }
+
+    /// Uwuifies the remaining data into the output.
+    fn finish(&mut self, output: &mut Vec<u8>) -> Result<(), UwuError> {
+        let segment = core::mem::take(&mut self.pending);
+        self.process(&segment, output)?;
+        if let Some(pipeline) = self.pipeline.as_mut() {
+            pipeline.finish(&self.engine, output);
+        }
+        Ok(())
+    }
+
+    fn process(&mut self, segment: &[u8], output: &mut Vec<u8>) -> Result<(), UwuError> {
+        let segment = core::str::from_utf8(segment)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+        // The whole input is not known upfront, so `Seed::Input` hashes the first segment instead
+        let pipeline = self.pipeline.get_or_insert_with(|| {
+            let rngs = FeatureRngs::new(self.engine.uwu.seed.create_rng(segment));
+            Pipeline::new(&self.engine, rngs)
+        });
+        pipeline.push(&self.engine, segment, output);
+        Ok(())
+    }
+}
+
+/// A reader that uwuifies the text read from the underlying reader.
+///
+/// The output is identical to calling [`crate::Uwu::uwuify`] with the whole input, as long as the seed
+/// does not depend on the input (see [`crate::Seed::Input`]).
+///
+/// Example:
+/// ```
+/// use std::io::Read;
+///
+/// let engine = uwu_rs::Uwu::new().compile().unwrap();
+/// let mut reader = engine.reader("Hello world!".as_bytes());
+/// let mut output = String::new();
+/// reader.read_to_string(&mut output).unwrap();
+/// ```
+pub struct UwuReader<R: Read> {
+    inner: R,
+    segmenter: Segmenter,
+    chunk: Box<[u8]>,
+    output: Vec<u8>,
+    pos: usize,
+    done: bool,
+}
+
+impl<R: Read> UwuReader<R> {
+    /// Creates a new reader that uwuifies the text read from `inner`.
+    pub fn new(engine: UwuEngine, inner: R) -> Self {
+        Self {
+            inner,
+            segmenter: Segmenter::new(engine),
+            chunk: vec![0; CHUNK_SIZE].into_boxed_slice(),
+            output: Vec::new(),
+            pos: 0,
+            done: false,
+        }
+    }
+
+    /// Unwraps this reader, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for UwuReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.pos == self.output.len() && !self.done {
+            self.output.clear();
+            self.pos = 0;
+
+            let read = self.inner.read(&mut self.chunk)?;
+            let result = if read == 0 {
+                self.done = true;
+                self.segmenter.finish(&mut self.output)
+            } else {
+                self.segmenter.push(&self.chunk[..read], &mut self.output)
+            };
+            result.map_err(into_io_error)?;
+        }
+
+        let available = &self.output[self.pos..];
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+/// A writer that uwuifies the text before writing it to the underlying writer.
+///
+/// Text is only written once a complete segment is available, so [`UwuWriter::finish`] must be
+/// called to write the remaining text. Dropping the writer will attempt to finish it, but any
+/// errors are ignored.
+///
+/// Example:
+/// ```
+/// use std::io::Write;
+///
+/// let engine = uwu_rs::Uwu::new().compile().unwrap();
+/// let mut writer = engine.writer(Vec::new());
+/// writer.write_all(b"Hello world!").unwrap();
+/// let output = writer.finish().unwrap();
+/// ```
+pub struct UwuWriter<W: Write> {
+    inner: Option<W>,
+    segmenter: Segmenter,
+    output: Vec<u8>,
+}
+
+impl<W: Write> UwuWriter<W> {
+    /// Creates a new writer that uwuifies the text before writing it to `inner`.
+    pub fn new(engine: UwuEngine, inner: W) -> Self {
+        Self {
+            inner: Some(inner),
+            segmenter: Segmenter::new(engine),
+            output: Vec::new(),
+        }
+    }
+
+    /// Uwuifies and writes the remaining text, returning the underlying writer.
+    pub fn finish(mut self) -> Result<W, UwuError> {
+        self.finish_inner()?;
+        Ok(self
+            .inner
+            .take()
+            .expect("writer is only taken when finishing"))
+    }
+
+    fn finish_inner(&mut self) -> Result<(), UwuError> {
+        self.output.clear();
+        self.segmenter.finish(&mut self.output)?;
+        if let Some(inner) = self.inner.as_mut() {
+            inner.write_all(&self.output)?;
+            inner.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for UwuWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.output.clear();
+        self.segmenter
+            .push(buf, &mut self.output)
+            .map_err(into_io_error)?;
+        if let Some(inner) = self.inner.as_mut() {
+            inner.write_all(&self.output)?;
+        }
+        Ok(buf.len())
+    }
+
+    /// Flushes the underlying writer. Text that does not form a complete segment yet is kept
+    /// buffered until more text is written or the writer is finished.
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self.inner.as_mut() {
+            Some(inner) => inner.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<W: Write> Drop for UwuWriter<W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.finish_inner();
+        }
+    }
+}
+
+/// Where to cut text without whitespace: after its last ASCII punctuation, or else at its last
+/// character boundary. Never 0 for text longer than a character.
+fn cut(text: &[u8]) -> usize {
+    match text
+        .iter()
+        .rposition(|byte| byte.is_ascii() && !is_word_byte(*byte))
+    {
+        Some(idx) => idx + 1,
+        // Continuation bytes of UTF-8 start with 0b10
+        None => text
+            .iter()
+            .rposition(|byte| byte & 0xc0 != 0x80)
+            .filter(|idx| *idx > 0)
+            .unwrap_or(text.len()),
+    }
+}
+
+fn into_io_error(err: UwuError) -> std::io::Error {
+    match err {
+        UwuError::Io(err) => err,
+        err => std::io::Error::other(err.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Seed, Uwu};
+
+    /// A reader that returns at most `max` bytes on each read, to exercise segment boundaries.
+    struct SlowReader<'a> {
+        data: &'a [u8],
+        max: usize,
+    }
+
+    impl Read for SlowReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let len = self.data.len().min(buf.len()).min(self.max);
+            buf[..len].copy_from_slice(&self.data[..len]);
+            self.data = &self.data[len..];
+            Ok(len)
+        }
+    }
+
+    //noinspection SpellCheckingInspection
+    #[test]
+    fn assert_stream_matches_uwuify() {
+        let inputs = [
+            "",
+            " ",
+            "hello",
+            "very nice, what a cute kitten! ",
+            "  My master, I have a question.  Nothing is   small! ",
+            "The quick brown fox jumps over the lazy dog.\nNo, really. Stupid fluff!",
+            "ΣΑΣ ΟΔΥΣΣΕΥΣ, ÉCOLE! Straße. привет мир",
+        ];
+        let uwus = [
+            Uwu::new(),
+            Uwu::builder().stutter(1).emojis(1).build(),
+            Uwu::builder().expressions().w_replace().build(),
+            Uwu::builder()
+                .lowercase()
+                .expressions()
+                .stutter(2)
+                .emojis(2)
+                .seed(Seed::Fixed(1))
+                .build(),
+        ];
+
+        for uwu in uwus {
+            for input in inputs {
+                let expected = uwu.uwuify(input).unwrap();
+                let engine = uwu.compile().unwrap();
+
+                for max in [1, 2, 3, 7, CHUNK_SIZE] {
+                    let reader = SlowReader {
+                        data: input.as_bytes(),
+                        max,
+                    };
+                    let mut output = Vec::new();
+                    uwu.uwuify_stream(reader, &mut output).unwrap();
+                    assert_eq!(String::from_utf8(output).unwrap(), expected);
+
+                    let mut writer = engine.writer(Vec::new());
+                    for chunk in input.as_bytes().chunks(max) {
+                        writer.write_all(chunk).unwrap();
+                    }
+                    let output = writer.finish().unwrap();
+                    assert_eq!(String::from_utf8(output).unwrap(), expected);
+                }
+            }
+        }
+    }
+
+    //noinspection SpellCheckingInspection
+    #[test]
+    fn assert_stream_without_spaces() {
+        let json = r#"{"level":"info","msg":"What a Lovely day","tags":["cute","fluff"]},"#;
+        let cjk = "こんにちは、世界！今日はいい天気ですね。";
+        let uwu = Uwu::new();
+        let engine = uwu.compile().unwrap();
+
+        for unit in [json, cjk, "Ω"] {
+            let input = unit.repeat(2 * 1024 * 1024 / unit.len());
+            let mut segmenter = Segmenter::new(engine.clone());
+            let mut output = Vec::new();
+            for chunk in input.as_bytes().chunks(CHUNK_SIZE - 1) {
+                segmenter.push(chunk, &mut output).unwrap();
+                assert!(segmenter.pending.len() <= MAX_SEGMENT, "{unit}");
+            }
+            segmenter.finish(&mut output).unwrap();
+            assert_eq!(
+                String::from_utf8(output).unwrap(),
+                uwu.uwuify(&input).unwrap(),
+                "{unit}"
+            );
+        }
+
+        // Line breaks and tabs end segments too
+        let mut segmenter = Segmenter::new(engine);
+        segmenter
+            .push(b"hello\nworld\tfoo\rbar", &mut Vec::new())
+            .unwrap();
+        assert_eq!(segmenter.pending, b"bar");
+    }
+
+    #[test]
+    fn assert_stream_invalid_utf8() {
+        let mut output = Vec::new();
+        let result = Uwu::new().uwuify_stream([b'a', 0xff, b' '].as_slice(), &mut output);
+        assert!(
+            matches!(result, Err(UwuError::Io(err)) if err.kind() == std::io::ErrorKind::InvalidData)
+        );
+    }
+}
@@ -0,0 +1,268 @@
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::tokenizer::{first_grapheme, is_grapheme_boundary};
+use crate::{pipeline, Chance, Uwu, UwuError};
+
+/// Specifies which words stutter and how, on top of the chance of the stutter feature.
+///
+/// Example:
+/// ```
+/// use uwu_rs::Uwu;
+///
+/// let uwu = Uwu::builder()
+///     .stutter(1)
+///     .stutter_min_length(2)
+///     .add_stutter_stopword("the")
+///     .stutter_consonant_clusters()
+///     .build();
+/// assert_eq!(uwu.uwuify("stop the cat, I said").unwrap(), "st-stop the c-cat, I s-said");
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct StutterOptions {
+    /// The minimum number of letters of a word that stutters, e.g. 2 skips 'a' and 'I'.
+    pub min_length: usize,
+    /// The words that never stutter, ignoring case. Words are matched after the expressions and
+    /// w replace features, and their w-replaced forms are skipped too.
+    pub stopwords: Vec<String>,
+    /// The maximum number of times a word stutters, e.g. 2 allows 'h-h-hello'.
+    pub max_repeats: u8,
+    /// The chance of stuttering once more, up to the maximum number of repeats.
+    pub repeat_chance: Chance,
+    /// Repeats the consonants a word starts with, e.g. 'stop' becomes 'st-stop', instead of its
+    /// first letter.
+    pub consonant_clusters: bool,
+    /// Specifies how the words that stutter are picked.
+    pub mode: StutterMode,
+}
+
+impl Default for StutterOptions {
+    fn default() -> Self {
+        Self {
+            min_length: 1,
+            stopwords: Vec::new(),
+            max_repeats: 1,
+            repeat_chance: Chance::NEVER,
+            consonant_clusters: false,
+            mode: StutterMode::Random,
+        }
+    }
+}
+
+impl StutterOptions {
+    /// Validates the options of an enabled stutter feature, naming the field in the error.
+    pub(crate) fn validate(&self) -> Result<(), UwuError> {
+        if self.max_repeats == 0 {
+            return Err(UwuError::InvalidConfig(String::from(
+                "stutter_options.max_repeats must be at least 1, got 0",
+            )));
+        }
+        if self.max_repeats > 1 {
+            self.repeat_chance
+                .validate("stutter_options.repeat_chance", "repeats are")?;
+        }
+        if self.mode == StutterMode::EveryNth(0) {
+            return Err(UwuError::InvalidConfig(String::from(
+                "stutter_options.mode must stutter every 1 or more words, got every 0",
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Specifies how the words that stutter are picked.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum StutterMode {
+    /// Words stutter at random, with the chance of the stutter feature.
+    #[default]
+    Random,
+    /// Every Nth word that may stutter does, e.g. 3 stutters the 3rd, 6th and 9th words, ignoring
+    /// the chance of the stutter feature.
+    EveryNth(u32),
+}
+
+/// The compiled stutter configuration, shared by the fused pipeline and the stutter stage so both
+/// pick the same words.
+#[derive(Clone, Debug)]
+pub(crate) struct StutterRules {
+    chance: Chance,
+    min_length: usize,
+    /// The lowercase stopwords, with their w-replaced forms.
+    stopwords: BTreeSet<String>,
+    max_repeats: u8,
+    repeat_chance: Chance,
+    consonant_clusters: bool,
+    mode: StutterMode,
+}
+
+impl StutterRules {
+    pub(crate) fn new(uwu: &Uwu) -> Self {
+        let options = &uwu.stutter_options;
+        let mut stopwords = BTreeSet::new();
+        for stopword in &options.stopwords {
+            let lowercase = stopword.to_lowercase();
+            if uwu.w_replace {
+                let replaced = lowercase.bytes().map(pipeline::w_replace).collect();
+                stopwords.extend(String::from_utf8(replaced));
+            }
+            stopwords.insert(lowercase);
+        }
+        Self {
+            chance: uwu.stutter_chance,
+            min_length: options.min_length,
+            stopwords,
+            max_repeats: options.max_repeats,
+            repeat_chance: options.repeat_chance,
+            consonant_clusters: options.consonant_clusters,
+            mode: options.mode,
+        }
+    }
+
+    /// Whether picking the words that stutter needs the whole word, rather than its first letter.
+    pub(crate) fn needs_word(&self) -> bool {
+        self.min_length > 1 || !self.stopwords.is_empty() || self.consonant_clusters
+    }
+
+    /// Decides whether a word after a stutter boundary stutters, returning how many times. Only
+    /// the first letter of the word is needed unless [`StutterRules::needs_word`].
+    ///
+    /// `count` is the number of words that could stutter so far, for the every Nth word mode.
+    pub(crate) fn draw(&self, word: &str, count: &mut u64, rng: &mut fastrand::Rng) -> u8 {
+        if !word.starts_with(char::is_alphabetic) {
+            return 0;
+        }
+        if self.min_length > 1 && word.graphemes(true).count() < self.min_length {
+            return 0;
+        }
+        if !self.stopwords.is_empty() && self.stopwords.contains(&word.to_lowercase()) {
+            return 0;
+        }
+
+        let stutters = match self.mode {
+            StutterMode::Random => self.chance.draw(rng),
+            StutterMode::EveryNth(n) => {
+                *count += 1;
+                count.is_multiple_of(u64::from(n))
+            }
+        };
+        if !stutters {
+            return 0;
+        }
+        let mut repeats = 1;
+        while repeats < self.max_repeats && self.repeat_chance.draw(rng) {
+            repeats += 1;
+        }
+        repeats
+    }
+
+    /// The start of the word that is repeated, e.g. 'h' in 'hello', or 'st' in 'stop' with
+    /// consonant clusters.
+    pub(crate) fn prefix<'a>(&self, word: &'a str) -> &'a str {
+        if self.consonant_clusters {
+            // The cluster must be followed by a vowel, e.g. not 'hmm' or 'ts\u{30c}'
+            let len = consonant_cluster_len(word);
+            let vowel =
+                word[len..].starts_with(char::is_alphabetic) && is_grapheme_boundary(word, len);
+            if len > 0 && vowel {
+                return &word[..len];
+            }
+        }
+        first_grapheme(word)
+    }
+}
+
+/// The length of the ASCII consonants the word starts with, where 'y' is only a consonant at the
+/// start, e.g. 'st' in 'stop' and 'style', and 'y' in 'yes'.
+fn consonant_cluster_len(word: &str) -> usize {
+    word.bytes()
+        .enumerate()
+        .take_while(|(idx, byte)| {
+            byte.is_ascii_alphabetic()
+                && !matches!(byte.to_ascii_lowercase(), b'a' | b'e' | b'i' | b'o' | b'u')
+                && (*idx == 0 || !byte.eq_ignore_ascii_case(&b'y'))
+        })
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //noinspection SpellCheckingInspection
+    #[test]
+    fn assert_stutter_options() {
+        let convert =
+            |builder: &mut crate::UwuBuilder, input: &str| builder.build().uwuify(input).unwrap();
+
+        let input = "a big red apple, I think";
+        let mut builder = Uwu::builder();
+        builder.stutter(1).stutter_min_length(2);
+        assert_eq!(
+            convert(&mut builder, input),
+            "a b-big r-red a-apple, I t-think"
+        );
+
+        // Stopwords are matched ignoring case, and after w replace
+        builder.w_replace().add_stutter_stopword("RED");
+        assert_eq!(
+            convert(&mut builder, input),
+            "a b-big wed a-appwe, I t-think"
+        );
+
+        let mut builder = Uwu::builder();
+        builder.stutter_consonant_clusters().stutter(1);
+        assert_eq!(
+            convert(&mut builder, "stop Three apples, shh style"),
+            "st-stop Thr-Three a-apples, s-shh st-style"
+        );
+
+        let mut builder = Uwu::builder();
+        builder.stutter_every(2).stutter_repeats(3, Chance::ALWAYS);
+        assert_eq!(
+            convert(&mut builder, "one two 3 four five six"),
+            "one t-t-t-two 3 four f-f-f-five six"
+        );
+
+        // Repeats are random, but never above the maximum
+        let mut builder = Uwu::builder();
+        builder.stutter(1).stutter_repeats(2, Chance::one_in(2));
+        let uwuified = convert(&mut builder, "hello hello hello hello hello hello");
+        assert!(uwuified.contains("h-h-hello") && uwuified.contains(" h-hello"));
+        assert!(!uwuified.contains("h-h-h-"));
+    }
+
+    #[test]
+    fn assert_stutter_options_validate() {
+        let error = |builder: &mut crate::UwuBuilder| builder.try_build().unwrap_err().to_string();
+
+        assert_eq!(
+            error(Uwu::builder().stutter(1).stutter_repeats(0, Chance::ALWAYS)),
+            "invalid configuration: stutter_options.max_repeats must be at least 1, got 0"
+        );
+        assert_eq!(
+            error(Uwu::builder().stutter(1).stutter_repeats(2, Chance::NEVER)),
+            "invalid configuration: stutter_options.repeat_chance must be greater than 0 when repeats are enabled"
+        );
+        assert_eq!(
+            error(Uwu::builder().stutter_every(0)),
+            "invalid configuration: stutter_options.mode must stutter every 1 or more words, got every 0"
+        );
+        // The options of a disabled feature are not validated
+        let mut builder = Uwu::builder();
+        builder.stutter_repeats(0, Chance::NEVER);
+        assert!(builder.try_build().is_ok());
+    }
+}
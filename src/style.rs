@@ -0,0 +1,306 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::{Protection, UwuBuilder, UwuError};
+
+/// A named configuration of the uwu algorithm, e.g. `light` or `catgirl`.
+///
+/// Example:
+/// ```
+/// use uwu_rs::{Style, StyleRegistry, UwuBuilder};
+///
+/// let mut builder = UwuBuilder::new();
+/// builder.expressions().w_replace().add_expression("hello", "henlo");
+///
+/// let mut styles = StyleRegistry::new();
+/// styles.register(Style::new("henlo", "Only says henlo", builder));
+/// let uwu = styles.builder("henlo").unwrap().build();
+/// assert_eq!(uwu.uwuify("hello world").unwrap(), "henwo wowwd");
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Style {
+    name: String,
+    description: String,
+    builder: UwuBuilder,
+}
+
+impl Style {
+    /// Creates a new style with the given name, a short description for front-ends, and the
+    /// configuration it stands for.
+    pub fn new<N: Into<String>, D: Into<String>>(
+        name: N,
+        description: D,
+        builder: UwuBuilder,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            builder,
+        }
+    }
+
+    /// The name of the style, e.g. `light`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// A short description of the style.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// A builder with the configuration of the style, which can be customised further.
+    pub fn builder(&self) -> UwuBuilder {
+        self.builder.clone()
+    }
+}
+
+/// The styles that can be looked up by name, e.g. to list them in a front-end.
+///
+/// The default registry contains the built-in styles:
+/// - `light`: only replaces 'l' and 'r' with 'w'
+/// - `medium`: the same as [`crate::Uwu::new`], protecting every kind of token
+/// - `maximum`: stutters every word, and adds emojis after every punctuation mark
+/// - `catgirl`: nyas every word, even the exceptions of `medium`, with cat emojis
+/// - `owo`: owo-flavored emojis
+///
+/// Example:
+/// ```
+/// use uwu_rs::StyleRegistry;
+///
+/// let styles = StyleRegistry::new();
+/// assert_eq!(styles.names().collect::<Vec<_>>(), ["light", "medium", "maximum", "catgirl", "owo"]);
+///
+/// let uwu = styles.builder("light").unwrap().build();
+/// assert_eq!(uwu.uwuify("Hello world!").unwrap(), "Hewwo wowwd!");
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StyleRegistry {
+    styles: Vec<Style>,
+}
+
+impl Default for StyleRegistry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register(Style::new(
+            "light",
+            "Only replaces 'l' and 'r' with 'w'",
+            light(),
+        ));
+        registry.register(Style::new(
+            "medium",
+            "The default uwu, with expressions, stutter and emojis",
+            medium(),
+        ));
+        registry.register(Style::new(
+            "maximum",
+            "Stutters every word and adds emojis everywhere",
+            maximum(),
+        ));
+        registry.register(Style::new(
+            "catgirl",
+            "Nyas all the way, with cat emojis",
+            catgirl(),
+        ));
+        registry.register(Style::new("owo", "OwO-flavored emojis", owo()));
+        registry
+    }
+}
+
+impl StyleRegistry {
+    /// Create a new registry with the built-in styles.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new registry without any styles.
+    pub fn empty() -> Self {
+        Self { styles: Vec::new() }
+    }
+
+    /// Adds a style, or replaces an existing one with the same name. Returns the previous style, if
+    /// any.
+    pub fn register(&mut self, style: Style) -> Option<Style> {
+        match self.position(&style.name) {
+            Some(idx) => Some(core::mem::replace(&mut self.styles[idx], style)),
+            None => {
+                self.styles.push(style);
+                None
+            }
+        }
+    }
+
+    /// Removes a style. Returns the removed style, if any.
+    pub fn remove(&mut self, name: &str) -> Option<Style> {
+        self.position(name).map(|idx| self.styles.remove(idx))
+    }
+
+    /// The style with the given name, ignoring the ASCII case.
+    pub fn get(&self, name: &str) -> Option<&Style> {
+        self.position(name).map(|idx| &self.styles[idx])
+    }
+
+    /// A builder with the configuration of the style with the given name, ignoring the ASCII case.
+    pub fn builder(&self, name: &str) -> Result<UwuBuilder, UwuError> {
+        self.get(name)
+            .map(Style::builder)
+            .ok_or_else(|| UwuError::UnknownStyle(name.to_string()))
+    }
+
+    /// The names of the styles, in the order they were registered.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.styles.iter().map(Style::name)
+    }
+
+    /// An iterator over the styles, in the order they were registered.
+    pub fn iter(&self) -> impl Iterator<Item = &Style> {
+        self.styles.iter()
+    }
+
+    /// The number of styles.
+    pub fn len(&self) -> usize {
+        self.styles.len()
+    }
+
+    /// Whether there are no styles.
+    pub fn is_empty(&self) -> bool {
+        self.styles.is_empty()
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.styles
+            .iter()
+            .position(|style| style.name.eq_ignore_ascii_case(name))
+    }
+}
+
+fn light() -> UwuBuilder {
+    let mut builder = UwuBuilder::new();
+    builder.w_replace().protection(Protection::ALL);
+    builder
+}
+
+fn medium() -> UwuBuilder {
+    let mut builder = UwuBuilder::new();
+    builder
+        .lowercase()
+        .expressions()
+        .nya()
+        .w_replace()
+        .stutter(4)
+        .emojis(1)
+        .protection(Protection::ALL);
+    builder
+}
+
+fn maximum() -> UwuBuilder {
+    let mut builder = medium();
+    builder
+        .stutter(1)
+        .add_punctuation(";")
+        .add_punctuation(":")
+        .add_punctuation("~");
+    builder
+}
+
+fn catgirl() -> UwuBuilder {
+    let mut builder = medium();
+    // Nya every word, e.g. 'one' becomes 'onye'
+    builder.clear_nya_exceptions().clear_emojis();
+    for emoji in [
+        "nyaa~~ ",
+        "mya ",
+        ":3 ",
+        "^•ﻌ•^ ",
+        "/(^•ω•^) ",
+        "(=^･ω･^=) ",
+        "=^..^= ",
+        "ฅ^•ﻌ•^ฅ ",
+    ] {
+        builder.add_emoji(emoji, 1);
+    }
+    builder
+}
+
+fn owo() -> UwuBuilder {
+    let mut builder = medium();
+    builder.clear_emojis();
+    for (emoji, weight) in [
+        ("OwO ", 3),
+        ("owo ", 2),
+        ("ÒwÓ ", 1),
+        ("0w0 ", 1),
+        ("(OwO) ", 1),
+        ("(・`ω´・) ", 1),
+    ] {
+        builder.add_emoji(emoji, weight);
+    }
+    builder
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Uwu;
+
+    //noinspection SpellCheckingInspection
+    #[test]
+    fn assert_builtin_styles() {
+        let styles = StyleRegistry::new();
+        assert_eq!(styles.len(), 5);
+        for style in styles.iter() {
+            assert!(style.builder().build_engine().is_ok(), "{}", style.name());
+        }
+
+        assert_eq!(
+            styles.builder("Medium").unwrap().build(),
+            Uwu {
+                protection: Protection::ALL,
+                ..Uwu::new()
+            }
+        );
+        assert!(matches!(
+            styles.builder("extreme"),
+            Err(UwuError::UnknownStyle(..))
+        ));
+
+        let input = "Hello world: banana? No one";
+        let convert = |name: &str| {
+            UwuBuilder::from_style(name)
+                .unwrap()
+                .build()
+                .uwuify(input)
+                .unwrap()
+        };
+        assert_eq!(convert("light"), "Hewwo wowwd: banana? No one");
+        assert_eq!(
+            convert("maximum"),
+            "h-hewwo w-wowwd: o.O b-banyanya? ^•ﻌ•^ n-nyo o-one"
+        );
+        assert_eq!(
+            convert("catgirl"),
+            "hewwo wowwd: b-banyanya? nyaa~~ nyo o-onye"
+        );
+    }
+
+    #[test]
+    fn assert_custom_styles() {
+        let mut styles = StyleRegistry::empty();
+        assert!(styles.is_empty());
+
+        let mut builder = UwuBuilder::new();
+        builder.lowercase();
+        assert_eq!(
+            styles.register(Style::new("quiet", "Lowercase", builder)),
+            None
+        );
+        assert_eq!(styles.get("QUIET").unwrap().description(), "Lowercase");
+
+        let replaced = styles.register(Style::new("quiet", "Nothing", UwuBuilder::new()));
+        assert_eq!(replaced.unwrap().description(), "Lowercase");
+        assert_eq!(styles.builder("quiet").unwrap(), UwuBuilder::new());
+
+        assert!(styles.remove("quiet").is_some());
+        assert!(styles.get("quiet").is_none());
+    }
+}
@@ -0,0 +1,396 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use unicode_segmentation::{GraphemeCursor, UnicodeSegmentation};
+
+use crate::UwuEngine;
+
+/// The kind of a [`Token`].
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum TokenKind {
+    /// A word, e.g. 'hello', "don't", '3.14' or a single ideograph like '猫'.
+    Word,
+    /// A run of whitespace, e.g. ' ' or '\r\n\t'.
+    Whitespace,
+    /// A single punctuation mark or symbol, e.g. '!' or '('.
+    Punctuation,
+    /// An emoji, including its modifiers and the emojis joined to it, e.g. '👍🏽' or '👩‍💻'.
+    Emoji,
+    /// A token passed through verbatim by an engine, e.g. a URL or a protected word.
+    Protected,
+}
+
+/// A segment of text, found by [`tokenize`] or [`UwuEngine::tokenize`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Token<'a> {
+    /// The kind of the token.
+    pub kind: TokenKind,
+    /// The text of the token.
+    pub text: &'a str,
+    /// The byte range of the token in the input.
+    pub range: Range<usize>,
+}
+
+/// Splits the text into words, whitespace, punctuation and emojis, following the word boundary
+/// rules of Unicode (UAX #29) in a simplified form:
+///
+/// - letters and digits form words, joined by apostrophes, periods and colons between letters,
+///   e.g. "don't", or by periods and commas between digits, e.g. '1,000.5'
+/// - ideographs and hiragana are words of a single character, as they are not separated by spaces
+/// - tokens are made of whole extended grapheme clusters, so combining marks, vowel signs,
+///   variation selectors and joiners stay with the character they modify
+/// - emojis include their skin tone modifiers and the emojis joined to them, and two regional
+///   indicators form a flag
+///
+/// Every byte of the input belongs to exactly one token, so joining the tokens produces the input.
+///
+/// Example:
+/// ```
+/// use uwu_rs::{tokenize, TokenKind};
+///
+/// let tokens: Vec<_> = tokenize("Don't stop!👍🏽").map(|token| (token.kind, token.text)).collect();
+/// assert_eq!(
+///     tokens,
+///     [
+///         (TokenKind::Word, "Don't"),
+///         (TokenKind::Whitespace, " "),
+///         (TokenKind::Word, "stop"),
+///         (TokenKind::Punctuation, "!"),
+///         (TokenKind::Emoji, "👍🏽"),
+///     ]
+/// );
+/// ```
+pub fn tokenize(input: &str) -> Tokens<'_> {
+    Tokens::new(input, Vec::new())
+}
+
+/// An iterator over the tokens of a text. See [`tokenize`].
+#[derive(Clone, Debug)]
+pub struct Tokens<'a> {
+    input: &'a str,
+    pos: usize,
+    /// The ranges returned as protected tokens, sorted and without overlaps.
+    protected: Vec<Range<usize>>,
+    next_protected: usize,
+}
+
+impl<'a> Tokens<'a> {
+    pub(crate) fn new(input: &'a str, protected: Vec<Range<usize>>) -> Self {
+        Self {
+            input,
+            pos: 0,
+            protected,
+            next_protected: 0,
+        }
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos == self.input.len() {
+            return None;
+        }
+        let start = self.pos;
+        let protected = self.protected.get(self.next_protected);
+        let (kind, end) = match protected {
+            Some(range) if range.start == start => {
+                self.next_protected += 1;
+                (TokenKind::Protected, range.end)
+            }
+            _ => {
+                // Tokens never extend into a protected range
+                let limit = protected.map_or(self.input.len(), |range| range.start);
+                scan(&self.input[start..limit], start)
+            }
+        };
+        self.pos = end;
+        Some(Token {
+            kind,
+            text: &self.input[start..end],
+            range: start..end,
+        })
+    }
+}
+
+impl UwuEngine {
+    /// Splits the text into tokens like [`tokenize`], where the tokens passed through verbatim by
+    /// this engine, e.g. URLs or protected words, are [`TokenKind::Protected`].
+    ///
+    /// Example:
+    /// ```
+    /// use uwu_rs::TokenKind;
+    ///
+    /// let engine = uwu_rs::Uwu::builder().protect_urls().build_engine().unwrap();
+    /// let token = engine.tokenize("see https://rust.rs").last().unwrap();
+    /// assert_eq!((token.kind, token.text), (TokenKind::Protected, "https://rust.rs"));
+    /// ```
+    pub fn tokenize<'a>(&self, input: &'a str) -> Tokens<'a> {
+        let protected = self.protected_ranges(input, &[]).unwrap_or_default();
+        Tokens::new(input, protected)
+    }
+}
+
+/// Finds the kind and the end of the token at the start of the text, which is not empty.
+fn scan(text: &str, offset: usize) -> (TokenKind, usize) {
+    // Tokens are made of whole grapheme clusters, classified by their first character
+    let mut graphemes = text
+        .grapheme_indices(true)
+        .map(|(idx, grapheme)| (idx, base(grapheme)))
+        .peekable();
+    let (_, first) = graphemes.next().unwrap();
+
+    let kind = if first.is_whitespace() {
+        while graphemes
+            .next_if(|(_, char)| char.is_whitespace())
+            .is_some()
+        {}
+        TokenKind::Whitespace
+    } else if is_regional_indicator(first) || is_pictographic(first) {
+        // Clusters already join flags, skin tone modifiers and emojis joined with a zero width
+        // joiner, e.g. '👩‍💻'
+        TokenKind::Emoji
+    } else if is_ideographic(first) {
+        TokenKind::Word
+    } else if is_word(first) {
+        let mut prev = first;
+        loop {
+            while let Some((_, char)) = graphemes.next_if(|(_, char)| is_word(*char)) {
+                prev = char;
+            }
+            // A separator only joins the characters around it, e.g. the apostrophe in "don't"
+            let Some(&(_, separator)) = graphemes.peek() else {
+                break;
+            };
+            let mut after = graphemes.clone();
+            after.next();
+            let joins = after.peek().is_some_and(|(_, next)| {
+                if prev.is_numeric() && next.is_numeric() {
+                    is_mid_number(separator)
+                } else {
+                    prev.is_alphabetic() && next.is_alphabetic() && is_mid_letter(separator)
+                }
+            });
+            if !joins {
+                break;
+            }
+            graphemes.next();
+        }
+        TokenKind::Word
+    } else {
+        TokenKind::Punctuation
+    };
+
+    let end = graphemes.peek().map_or(text.len(), |(idx, _)| *idx);
+    (kind, offset + end)
+}
+
+/// The first character of a grapheme cluster, which is not empty.
+fn base(grapheme: &str) -> char {
+    grapheme.chars().next().unwrap_or_default()
+}
+
+fn is_word(char: char) -> bool {
+    (char.is_alphanumeric() || char == '_') && !is_ideographic(char)
+}
+
+/// Whether the character joins the letters around it into a single word.
+fn is_mid_letter(char: char) -> bool {
+    matches!(
+        char,
+        '\'' | '.' | ':' | '·' | '‘' | '’' | '\u{2024}' | '\u{2027}'
+    )
+}
+
+/// Whether the character joins the digits around it into a single number.
+fn is_mid_number(char: char) -> bool {
+    matches!(char, '\'' | '.' | ',' | ';' | '’' | '\u{2024}')
+}
+
+/// The first extended grapheme cluster of the text, i.e. its first character with the marks and
+/// modifiers that follow it, e.g. 'é' in 'e\u{301}lan' or 'কি' in 'কিছু'.
+pub(crate) fn first_grapheme(text: &str) -> &str {
+    text.graphemes(true).next().unwrap_or_default()
+}
+
+/// Whether an extended grapheme cluster of the text starts or ends at the byte index.
+pub(crate) fn is_grapheme_boundary(text: &str, idx: usize) -> bool {
+    GraphemeCursor::new(idx, text.len(), true)
+        .is_boundary(text, 0)
+        .unwrap_or(true)
+}
+
+/// Whether the character is an ideograph or a hiragana, which form words on their own.
+fn is_ideographic(char: char) -> bool {
+    matches!(
+        char as u32,
+        0x3040..=0x309f
+            | 0x3400..=0x4dbf
+            | 0x4e00..=0x9fff
+            | 0xf900..=0xfaff
+            | 0x20000..=0x3ffff
+    )
+}
+
+fn is_regional_indicator(char: char) -> bool {
+    matches!(char as u32, 0x1f1e6..=0x1f1ff)
+}
+
+/// Whether the character is an emoji, approximating the `Extended_Pictographic` property.
+fn is_pictographic(char: char) -> bool {
+    matches!(
+        char as u32,
+        0x00a9
+            | 0x00ae
+            | 0x203c
+            | 0x2049
+            | 0x2122
+            | 0x2139
+            | 0x2194..=0x2199
+            | 0x21a9..=0x21aa
+            | 0x231a..=0x231b
+            | 0x2328
+            | 0x23cf
+            | 0x23e9..=0x23f3
+            | 0x23f8..=0x23fa
+            | 0x24c2
+            | 0x25aa..=0x25ab
+            | 0x25b6
+            | 0x25c0
+            | 0x25fb..=0x25fe
+            | 0x2600..=0x27bf
+            | 0x2934..=0x2935
+            | 0x2b05..=0x2b07
+            | 0x2b1b..=0x2b1c
+            | 0x2b50
+            | 0x2b55
+            | 0x3030
+            | 0x303d
+            | 0x3297
+            | 0x3299
+            | 0x1f000..=0x1f1e5
+            | 0x1f200..=0x1f3fa
+            | 0x1f400..=0x1faff
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Uwu;
+    use alloc::string::String;
+
+    fn tokens(input: &str) -> Vec<(TokenKind, &str)> {
+        tokenize(input)
+            .map(|token| (token.kind, token.text))
+            .collect()
+    }
+
+    //noinspection SpellCheckingInspection
+    #[test]
+    fn assert_tokenize() {
+        use TokenKind::*;
+
+        assert_eq!(tokens(""), []);
+        assert_eq!(
+            tokens("  Hello,\r\n\t(world)..."),
+            [
+                (Whitespace, "  "),
+                (Word, "Hello"),
+                (Punctuation, ","),
+                (Whitespace, "\r\n\t"),
+                (Punctuation, "("),
+                (Word, "world"),
+                (Punctuation, ")"),
+                (Punctuation, "."),
+                (Punctuation, "."),
+                (Punctuation, "."),
+            ]
+        );
+        assert_eq!(
+            tokens("it’s 1,000.5 e.g. snake_case"),
+            [
+                (Word, "it’s"),
+                (Whitespace, " "),
+                (Word, "1,000.5"),
+                (Whitespace, " "),
+                (Word, "e.g"),
+                (Punctuation, "."),
+                (Whitespace, " "),
+                (Word, "snake_case"),
+            ]
+        );
+        // Combining marks stay with their letter, and ideographs are words of their own
+        assert_eq!(
+            tokens("cafe\u{301} привет 猫が好き"),
+            [
+                (Word, "cafe\u{301}"),
+                (Whitespace, " "),
+                (Word, "привет"),
+                (Whitespace, " "),
+                (Word, "猫"),
+                (Word, "が"),
+                (Word, "好"),
+                (Word, "き"),
+            ]
+        );
+        // Vowel signs and viramas never split a word, e.g. in Tamil, Bengali or Devanagari
+        assert_eq!(
+            tokens("தமிழ், কিছু हिंदी!"),
+            [
+                (Word, "தமிழ்"),
+                (Punctuation, ","),
+                (Whitespace, " "),
+                (Word, "কিছু"),
+                (Whitespace, " "),
+                (Word, "हिंदी"),
+                (Punctuation, "!"),
+            ]
+        );
+        assert_eq!(
+            tokens("hi👋🏽👩‍💻🇵🇹❤️!"),
+            [
+                (Word, "hi"),
+                (Emoji, "👋🏽"),
+                (Emoji, "👩‍💻"),
+                (Emoji, "🇵🇹"),
+                (Emoji, "❤️"),
+                (Punctuation, "!"),
+            ]
+        );
+
+        // Every byte belongs to a token
+        let input = "  ÉCOLE's\u{3000}(l'été)  “yes” 🤷🏻‍♀️\u{301}x ";
+        assert_eq!(
+            tokenize(input).map(|token| token.text).collect::<String>(),
+            input
+        );
+        for token in tokenize(input) {
+            assert_eq!(&input[token.range], token.text);
+        }
+    }
+
+    #[test]
+    fn assert_tokenize_protected() {
+        let mut builder = Uwu::builder();
+        builder.protect_urls().protect_word("Rust");
+        let engine = builder.build_engine().unwrap();
+
+        let input = "Rust:https://rust.rs/ Rusty";
+        let tokens: Vec<_> = engine
+            .tokenize(input)
+            .map(|token| (token.kind, token.text))
+            .collect();
+        assert_eq!(
+            tokens,
+            [
+                (TokenKind::Protected, "Rust"),
+                (TokenKind::Punctuation, ":"),
+                (TokenKind::Protected, "https://rust.rs/"),
+                (TokenKind::Whitespace, " "),
+                (TokenKind::Word, "Rusty"),
+            ]
+        );
+    }
+}
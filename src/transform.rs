@@ -0,0 +1,920 @@
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+use aho_corasick::automaton::{Automaton, StateID};
+use aho_corasick::dfa::DFA;
+use aho_corasick::{AhoCorasick, Anchored};
+
+use crate::case::Case;
+use crate::dictionary::{is_word_byte, Form, BOUNDARY};
+use crate::engine::into_string;
+use crate::seed::FeatureRngs;
+use crate::stutter::StutterRules;
+use crate::{
+    pipeline, tokenize, Chance, EmojiSet, Locale, ProtectedSpan, PunctuationSet, Shared, UwuEngine,
+    UwuError,
+};
+
+/// A stage of an [`UwuPipeline`], which transforms the text produced by the previous stage.
+///
+/// Protected tokens, words and spans split the input into pieces, and every stage transforms each
+/// piece separately, as is. Only the built-in stages see the start and end of the input as a
+/// space, e.g. so its first word stutters.
+///
+/// Example:
+/// ```
+/// use uwu_rs::{Transform, TransformContext};
+///
+/// struct Slang;
+///
+/// impl Transform for Slang {
+///     fn name(&self) -> &str {
+///         "slang"
+///     }
+///
+///     fn transform(&self, text: &str, _context: &mut TransformContext) -> String {
+///         text.split(' ')
+///             .map(|word| if word == "synergy" { "vibes" } else { word })
+///             .collect::<Vec<_>>()
+///             .join(" ")
+///     }
+/// }
+/// ```
+pub trait Transform: Send + Sync {
+    /// The name of the stage, used to find it in a pipeline. The built-in stages are named
+    /// `lowercase`, `expressions`, `nya`, `w_replace`, `stutter` and `emojis`.
+    fn name(&self) -> &str;
+
+    /// Transforms the text produced by the previous stage.
+    fn transform(&self, text: &str, context: &mut TransformContext) -> String;
+}
+
+/// The state shared by the stages of a pipeline while they uwuify an input.
+#[derive(Clone, Debug)]
+pub struct TransformContext {
+    pub(crate) rngs: FeatureRngs,
+    /// The number of words that could stutter so far, for the every Nth word mode.
+    pub(crate) stutter_count: u64,
+    /// The ranges of the text replaced by the expressions stage, for the stage right after it, so
+    /// the nya stage skips them.
+    pub(crate) replaced: Vec<Range<usize>>,
+    rng: fastrand::Rng,
+}
+
+impl TransformContext {
+    fn new(mut rng: fastrand::Rng) -> Self {
+        let rngs = FeatureRngs::new(rng.clone());
+        Self {
+            rngs,
+            stutter_count: 0,
+            replaced: Vec::new(),
+            rng: fastrand::Rng::with_seed(rng.u64(..)),
+        }
+    }
+
+    /// The random number generator for custom stages, seeded like the built-in stages, so the same
+    /// input always produces the same output with a fixed [`crate::Seed`].
+    pub fn rng(&mut self) -> &mut fastrand::Rng {
+        &mut self.rng
+    }
+}
+
+/// The stages of the uwu algorithm, in the order they run by default.
+const BUILTINS: [Builtin; 6] = [
+    Builtin::Lowercase,
+    Builtin::Expressions,
+    Builtin::Nya,
+    Builtin::WReplace,
+    Builtin::Stutter,
+    Builtin::Emojis,
+];
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Builtin {
+    Lowercase,
+    Expressions,
+    Nya,
+    WReplace,
+    Stutter,
+    Emojis,
+}
+
+impl Builtin {
+    fn name(self) -> &'static str {
+        match self {
+            Builtin::Lowercase => "lowercase",
+            Builtin::Expressions => "expressions",
+            Builtin::Nya => "nya",
+            Builtin::WReplace => "w_replace",
+            Builtin::Stutter => "stutter",
+            Builtin::Emojis => "emojis",
+        }
+    }
+
+    fn is_enabled(self, engine: &UwuEngine) -> bool {
+        let uwu = &engine.uwu;
+        match self {
+            Builtin::Lowercase => uwu.lowercase,
+            Builtin::Expressions => uwu.expressions,
+            Builtin::Nya => uwu.nya,
+            Builtin::WReplace => uwu.w_replace,
+            Builtin::Stutter => uwu.stutter,
+            Builtin::Emojis => uwu.emojis,
+        }
+    }
+
+    fn create(self, engine: &UwuEngine) -> Result<Shared<dyn Transform>, UwuError> {
+        let uwu = &engine.uwu;
+        let stage: Shared<dyn Transform> = match self {
+            Builtin::Lowercase => Shared::new(Lowercase { locale: uwu.locale }),
+            Builtin::Expressions => {
+                // Other stages may run first, so the case of the text is unknown. The stages own
+                // their data, since they must be `Send` even where the engine is not
+                let forms = engine.expressions_forms.to_vec();
+                let matcher = AhoCorasick::builder()
+                    .ascii_case_insensitive(true)
+                    .match_kind(uwu.dictionary.match_kind().into())
+                    .build(forms.iter().map(|form| &form.pattern))?;
+                Shared::new(Expressions { matcher, forms })
+            }
+            Builtin::Nya => Shared::new(Nya {
+                exceptions: BTreeSet::clone(&engine.nya_exceptions),
+            }),
+            Builtin::WReplace => Shared::new(WReplace),
+            Builtin::Stutter => Shared::new(Stutter {
+                rules: StutterRules::clone(&engine.stutter),
+            }),
+            Builtin::Emojis => Shared::new(Emojis {
+                punctuation: DFA::clone(&engine.punctuation),
+                punctuation_start: engine.punctuation_start,
+                punctuation_set: uwu.punctuation_set.clone(),
+                emoji_set: uwu.emoji_set.clone(),
+                chance: uwu.emojis_chance,
+            }),
+        };
+        Ok(stage)
+    }
+}
+
+#[derive(Clone)]
+enum Stage {
+    Builtin(Builtin),
+    Custom(Shared<dyn Transform>),
+}
+
+impl Stage {
+    fn name(&self) -> &str {
+        match self {
+            Stage::Builtin(builtin) => builtin.name(),
+            Stage::Custom(stage) => stage.name(),
+        }
+    }
+}
+
+/// A builder for [`UwuPipeline`], which starts with the built-in stages enabled by the engine.
+///
+/// Example:
+/// ```
+/// use uwu_rs::{Transform, TransformContext, Uwu, UwuPipelineBuilder};
+///
+/// struct Slang;
+///
+/// impl Transform for Slang {
+///     fn name(&self) -> &str {
+///         "slang"
+///     }
+///
+///     fn transform(&self, text: &str, _context: &mut TransformContext) -> String {
+///         text.split(' ')
+///             .map(|word| if word == "synergy" { "vibes" } else { word })
+///             .collect::<Vec<_>>()
+///             .join(" ")
+///     }
+/// }
+///
+/// let engine = Uwu::builder().lowercase().w_replace().stutter(1).build_engine().unwrap();
+/// let pipeline = UwuPipelineBuilder::new(engine)
+///     .insert_after("lowercase", Slang)
+///     .remove("stutter")
+///     .build()
+///     .unwrap();
+/// assert_eq!(pipeline.uwuify("Real synergy").unwrap(), "weaw vibes");
+/// ```
+#[derive(Clone)]
+pub struct UwuPipelineBuilder {
+    engine: UwuEngine,
+    stages: Vec<Stage>,
+    error: Option<String>,
+}
+
+impl UwuPipelineBuilder {
+    /// Creates a new builder with the built-in stages enabled by the engine, in their default order.
+    pub fn new(engine: UwuEngine) -> Self {
+        let stages = BUILTINS
+            .into_iter()
+            .filter(|builtin| builtin.is_enabled(&engine))
+            .map(Stage::Builtin)
+            .collect();
+        Self {
+            engine,
+            stages,
+            error: None,
+        }
+    }
+
+    /// The names of the stages, in the order they run.
+    pub fn names(&self) -> Vec<&str> {
+        self.stages.iter().map(Stage::name).collect()
+    }
+
+    /// Adds a stage after every other stage.
+    pub fn push<T: Transform + 'static>(&mut self, stage: T) -> &mut Self {
+        let idx = self.stages.len();
+        self.insert(idx, Stage::Custom(Shared::new(stage)))
+    }
+
+    /// Adds a stage right before the stage with the given name.
+    pub fn insert_before<T: Transform + 'static>(&mut self, name: &str, stage: T) -> &mut Self {
+        match self.position(name) {
+            Some(idx) => self.insert(idx, Stage::Custom(Shared::new(stage))),
+            None => self,
+        }
+    }
+
+    /// Adds a stage right after the stage with the given name.
+    pub fn insert_after<T: Transform + 'static>(&mut self, name: &str, stage: T) -> &mut Self {
+        match self.position(name) {
+            Some(idx) => self.insert(idx + 1, Stage::Custom(Shared::new(stage))),
+            None => self,
+        }
+    }
+
+    /// Removes the stage with the given name, e.g. `stutter`.
+    pub fn remove(&mut self, name: &str) -> &mut Self {
+        if let Some(idx) = self.position(name) {
+            self.stages.remove(idx);
+        }
+        self
+    }
+
+    /// Moves the stage with the given name right before the `other` stage.
+    pub fn move_before(&mut self, name: &str, other: &str) -> &mut Self {
+        self.move_stage(name, other, 0)
+    }
+
+    /// Moves the stage with the given name right after the `other` stage.
+    pub fn move_after(&mut self, name: &str, other: &str) -> &mut Self {
+        self.move_stage(name, other, 1)
+    }
+
+    /// Builds a new pipeline. Fails if a stage was not found, a name is used by multiple stages, or
+    /// a built-in stage cannot be created.
+    pub fn build(&self) -> Result<UwuPipeline, UwuError> {
+        if let Some(error) = &self.error {
+            return Err(UwuError::InvalidPipeline(error.clone()));
+        }
+
+        let default = BUILTINS
+            .into_iter()
+            .filter(|builtin| builtin.is_enabled(&self.engine))
+            .map(Stage::Builtin);
+        let fused = self.stages.len() == default.clone().count()
+            && self.stages.iter().zip(default).all(|(stage, builtin)| {
+                matches!((stage, builtin), (Stage::Builtin(a), Stage::Builtin(b)) if *a == b)
+            });
+
+        let stages = self
+            .stages
+            .iter()
+            .map(|stage| match stage {
+                Stage::Builtin(builtin) => Ok((builtin.create(&self.engine)?, true)),
+                Stage::Custom(stage) => Ok((Shared::clone(stage), false)),
+            })
+            .collect::<Result<_, UwuError>>()?;
+        Ok(UwuPipeline {
+            engine: self.engine.clone(),
+            stages,
+            fused,
+        })
+    }
+
+    fn position(&mut self, name: &str) -> Option<usize> {
+        let idx = self.stages.iter().position(|stage| stage.name() == name);
+        if idx.is_none() && self.error.is_none() {
+            self.error = Some(format!("unknown stage: {name}"));
+        }
+        idx
+    }
+
+    fn insert(&mut self, idx: usize, stage: Stage) -> &mut Self {
+        if self.stages.iter().any(|other| other.name() == stage.name()) {
+            if self.error.is_none() {
+                self.error = Some(format!("duplicate stage: {}", stage.name()));
+            }
+            return self;
+        }
+        self.stages.insert(idx, stage);
+        self
+    }
+
+    fn move_stage(&mut self, name: &str, other: &str, offset: usize) -> &mut Self {
+        let (Some(idx), Some(_)) = (self.position(name), self.position(other)) else {
+            return self;
+        };
+        let stage = self.stages.remove(idx);
+        let other_idx = self.position(other).unwrap_or_default();
+        self.stages.insert(other_idx + offset, stage);
+        self
+    }
+}
+
+impl fmt::Debug for UwuPipelineBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UwuPipelineBuilder")
+            .field("stages", &self.names())
+            .finish_non_exhaustive()
+    }
+}
+
+/// An uwu algorithm made of reorderable stages, including custom ones.
+///
+/// Protected tokens, words and spans are passed through verbatim, and every stage runs on the text
+/// between them. While the built-in stages run in their default order, the input is uwuified in a
+/// single pass exactly like [`UwuEngine::uwuify`].
+#[derive(Clone)]
+pub struct UwuPipeline {
+    engine: UwuEngine,
+    /// The stages, and whether each is a built-in stage, which sees the padding of the input.
+    stages: Vec<(Shared<dyn Transform>, bool)>,
+    /// Whether the stages are the built-in ones in their default order.
+    fused: bool,
+}
+
+impl UwuPipeline {
+    /// The names of the stages, in the order they run.
+    pub fn names(&self) -> Vec<&str> {
+        self.stages.iter().map(|(stage, _)| stage.name()).collect()
+    }
+
+    /// Converts the input text into an uwuified version.
+    pub fn uwuify<S: AsRef<str>>(&self, input: S) -> Result<String, UwuError> {
+        self.uwuify_protecting(input, &[])
+    }
+
+    /// Converts the input text into an uwuified version, passing the given spans through verbatim.
+    pub fn uwuify_protecting<S: AsRef<str>>(
+        &self,
+        input: S,
+        spans: &[ProtectedSpan],
+    ) -> Result<String, UwuError> {
+        if self.fused {
+            self.engine.uwuify_protecting(input, spans)
+        } else {
+            self.run_stages(input.as_ref(), spans)
+        }
+    }
+
+    /// Runs each stage in a separate pass over the text between the protected ranges.
+    pub(crate) fn run_stages(
+        &self,
+        input: &str,
+        spans: &[ProtectedSpan],
+    ) -> Result<String, UwuError> {
+        let protected = self.engine.protected_ranges(input, spans)?;
+        let mut context = TransformContext::new(self.engine.uwu.seed.create_rng(input));
+
+        let mut output = String::with_capacity(input.len() + input.len() / 2);
+        let mut copied = 0;
+        for range in protected {
+            let text = &input[copied..range.start];
+            output.push_str(&self.transform(text, copied == 0, false, &mut context));
+            output.push_str(&input[range.clone()]);
+            copied = range.end;
+        }
+        let text = &input[copied..];
+        output.push_str(&self.transform(text, copied == 0, true, &mut context));
+        Ok(output)
+    }
+
+    fn transform(
+        &self,
+        text: &str,
+        at_start: bool,
+        at_end: bool,
+        context: &mut TransformContext,
+    ) -> String {
+        // The built-in stages see the start and end of the input as a space, so the text is padded
+        // while they run
+        let pad = |text: &mut String| {
+            if at_start {
+                text.insert(0, ' ');
+            }
+            if at_end {
+                text.push(' ');
+            }
+        };
+        let unpad = |text: &mut String| {
+            if at_end && text.ends_with(' ') {
+                text.pop();
+            }
+            if at_start && text.starts_with(' ') {
+                text.remove(0);
+            }
+        };
+
+        let mut text = String::from(text);
+        let mut padded = false;
+        // The replaced ranges only hold for the text right after the expressions stage
+        context.replaced.clear();
+        for (stage, builtin) in &self.stages {
+            if *builtin != padded {
+                padded = *builtin;
+                if padded {
+                    pad(&mut text);
+                } else {
+                    unpad(&mut text);
+                }
+            }
+            text = stage.transform(&text, context);
+            if stage.name() != Builtin::Expressions.name() {
+                context.replaced.clear();
+            }
+        }
+        if padded {
+            unpad(&mut text);
+        }
+        text
+    }
+}
+
+impl fmt::Debug for UwuPipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UwuPipeline")
+            .field("stages", &self.names())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Case folds the text, e.g. 'Hello' becomes 'hello'.
+struct Lowercase {
+    locale: Locale,
+}
+
+impl Transform for Lowercase {
+    fn name(&self) -> &str {
+        Builtin::Lowercase.name()
+    }
+
+    fn transform(&self, text: &str, _context: &mut TransformContext) -> String {
+        self.locale.fold_case(text)
+    }
+}
+
+/// Replaces expressions, e.g. 'what' becomes 'nani'.
+struct Expressions {
+    matcher: AhoCorasick,
+    forms: Vec<Form>,
+}
+
+impl Transform for Expressions {
+    fn name(&self) -> &str {
+        Builtin::Expressions.name()
+    }
+
+    fn transform(&self, text: &str, context: &mut TransformContext) -> String {
+        let text = text.as_bytes();
+        context.replaced.clear();
+
+        // Insert a boundary byte wherever a word starts or ends, remembering the real index of
+        // every virtual byte
+        let mut virtual_text = Vec::with_capacity(text.len() + text.len() / 2);
+        let mut real_idx = Vec::with_capacity(virtual_text.capacity());
+        let mut word = false;
+        for (idx, byte) in text.iter().enumerate() {
+            if is_word_byte(*byte) != word {
+                word = !word;
+                virtual_text.push(BOUNDARY);
+                real_idx.push(idx);
+            }
+            virtual_text.push(*byte);
+            real_idx.push(idx);
+        }
+        if word {
+            virtual_text.push(BOUNDARY);
+        }
+        real_idx.push(text.len());
+
+        let mut out = Vec::with_capacity(text.len());
+        let mut copied = 0;
+        for mat in self.matcher.find_iter(&virtual_text) {
+            let form = &self.forms[mat.pattern().as_usize()];
+            // A leading boundary belongs to the byte after it
+            let start = real_idx[mat.start()];
+            let end = start + form.len;
+            out.extend_from_slice(&text[copied..start]);
+            let replaced_start = out.len();
+            out.extend(Case::detect(&text[start..end], text.get(end)).apply(&form.replacement));
+            context.replaced.push(replaced_start..out.len());
+            copied = end;
+        }
+        out.extend_from_slice(&text[copied..]);
+        into_string(out)
+    }
+}
+
+/// Adds a 'y' between an 'n' and a vowel, e.g. 'nice anime' becomes 'nyice anyime', except in
+/// the exceptions and the replacements of the expressions stage right before it.
+struct Nya {
+    exceptions: BTreeSet<String>,
+}
+
+impl Transform for Nya {
+    fn name(&self) -> &str {
+        Builtin::Nya.name()
+    }
+
+    fn transform(&self, text: &str, context: &mut TransformContext) -> String {
+        let mut out = String::with_capacity(text.len() + text.len() / 8);
+        let mut copied = 0;
+        for range in context.replaced.iter().chain([&(text.len()..text.len())]) {
+            // A word that goes on after a replacement is not a whole word, e.g. 'uick' in 'qwuick'
+            let after_word = text[..copied].bytes().next_back().is_some_and(is_word_byte);
+            self.push_words(&text[copied..range.start], after_word, &mut out);
+            out.push_str(&text[range.clone()]);
+            copied = range.end;
+        }
+        out
+    }
+}
+
+impl Nya {
+    fn push_words(&self, text: &str, after_word: bool, out: &mut String) {
+        let words = text
+            .as_bytes()
+            .chunk_by(|a, b| is_word_byte(*a) == is_word_byte(*b));
+        for (idx, word) in words.enumerate() {
+            // Words only end at ASCII bytes, so they are always valid UTF-8
+            let word = core::str::from_utf8(word).unwrap_or_default();
+            let whole = idx > 0 || !after_word;
+            if whole && self.exceptions.contains(&word.to_lowercase()) {
+                out.push_str(word);
+                continue;
+            }
+            let mut prev = None;
+            for char in word.chars() {
+                if let Some(n @ ('n' | 'N')) = prev {
+                    if char.is_ascii() && pipeline::is_vowel(char as u8) {
+                        out.push(pipeline::nya_y(n as u8, char as u8) as char);
+                    }
+                }
+                out.push(char);
+                prev = Some(char);
+            }
+        }
+    }
+}
+
+/// Replaces 'l' and 'r' with 'w', e.g. 'lovely' becomes 'wovewy'.
+struct WReplace;
+
+impl Transform for WReplace {
+    fn name(&self) -> &str {
+        Builtin::WReplace.name()
+    }
+
+    fn transform(&self, text: &str, _context: &mut TransformContext) -> String {
+        text.chars()
+            .map(|char| match char {
+                'l' | 'r' | 'L' | 'R' => pipeline::w_replace(char as u8) as char,
+                _ => char,
+            })
+            .collect()
+    }
+}
+
+/// Adds stutter to words that start with a letter, repeating their first grapheme, e.g. 'hello'
+/// becomes 'h-hello' and 'élan' becomes 'é-élan'. Words are found by [`tokenize`].
+struct Stutter {
+    rules: StutterRules,
+}
+
+impl Transform for Stutter {
+    fn name(&self) -> &str {
+        Builtin::Stutter.name()
+    }
+
+    fn transform(&self, text: &str, context: &mut TransformContext) -> String {
+        let mut out = String::with_capacity(text.len() + text.len() / 4);
+        let mut after_space = false;
+        for token in tokenize(text) {
+            if after_space {
+                let repeats = self.rules.draw(
+                    token.text,
+                    &mut context.stutter_count,
+                    &mut context.rngs.stutter,
+                );
+                for _ in 0..repeats {
+                    out.push_str(self.rules.prefix(token.text));
+                    out.push('-');
+                }
+            }
+            out.push_str(token.text);
+            after_space = token
+                .text
+                .chars()
+                .next_back()
+                .is_some_and(pipeline::is_stutter_boundary);
+        }
+        out
+    }
+}
+
+/// Adds emojis after punctuation, e.g. 'goodbye.' becomes 'goodbye. OwO'.
+struct Emojis {
+    punctuation: DFA,
+    punctuation_start: StateID,
+    punctuation_set: PunctuationSet,
+    emoji_set: EmojiSet,
+    chance: Chance,
+}
+
+impl Transform for Emojis {
+    fn name(&self) -> &str {
+        Builtin::Emojis.name()
+    }
+
+    fn transform(&self, text: &str, context: &mut TransformContext) -> String {
+        let automaton = &self.punctuation;
+        let mut out = String::with_capacity(text.len() + text.len() / 2);
+        let mut sid = self.punctuation_start;
+        // Whether the previous characters end with punctuation, and if it is standalone
+        let mut armed = None;
+        for char in text.chars() {
+            let mut emoji_after = None;
+            if let Some(standalone) = armed.take() {
+                if (char.is_whitespace() || standalone)
+                    && self.chance.draw(&mut context.rngs.emojis)
+                {
+                    let emoji = self.emoji_set.choose(&mut context.rngs.emojis);
+                    if char == ' ' {
+                        // The emoji goes after the space, and ends with its own space
+                        emoji_after = Some(emoji);
+                    } else if char.is_whitespace() {
+                        // The emoji goes before other whitespace, e.g. a line break
+                        out.push(' ');
+                        out.push_str(emoji.trim_end());
+                    } else {
+                        out.push_str(emoji);
+                    }
+                }
+            }
+            out.push(char);
+            for byte in char.encode_utf8(&mut [0; 4]).bytes() {
+                sid = automaton.next_state(Anchored::No, sid, byte);
+                if automaton.is_match(sid) {
+                    armed = Some((0..automaton.match_len(sid)).any(|idx| {
+                        let pid = automaton.match_pattern(sid, idx);
+                        self.punctuation_set.is_standalone(pid.as_usize())
+                    }));
+                }
+            }
+            out.extend(emoji_after);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Uwu;
+
+    struct Slang;
+
+    impl Transform for Slang {
+        fn name(&self) -> &str {
+            "slang"
+        }
+
+        fn transform(&self, text: &str, context: &mut TransformContext) -> String {
+            let suffix = if context.rng().bool() { "!!" } else { "!" };
+            text.replace(" synergy", &format!(" vibes{suffix}"))
+        }
+    }
+
+    //noinspection SpellCheckingInspection
+    #[test]
+    fn assert_custom_stages() {
+        let engine = Uwu::builder()
+            .lowercase()
+            .expressions()
+            .nya()
+            .w_replace()
+            .stutter(1)
+            .emojis(1)
+            .clear_emojis()
+            .add_emoji("OwO ", 1)
+            .protect_mentions()
+            .build_engine()
+            .unwrap();
+
+        let pipeline = UwuPipelineBuilder::new(engine.clone()).build().unwrap();
+        assert_eq!(
+            pipeline.names(),
+            [
+                "lowercase",
+                "expressions",
+                "nya",
+                "w_replace",
+                "stutter",
+                "emojis"
+            ]
+        );
+        let input = "What synergy, @Laura? Really";
+        assert_eq!(
+            pipeline.uwuify(input).unwrap(),
+            engine.uwuify(input).unwrap()
+        );
+
+        let pipeline = UwuPipelineBuilder::new(engine.clone())
+            .insert_after("lowercase", Slang)
+            .remove("nya")
+            .remove("stutter")
+            .move_before("w_replace", "expressions")
+            .build()
+            .unwrap();
+        assert_eq!(
+            pipeline.names(),
+            ["lowercase", "slang", "w_replace", "expressions", "emojis"]
+        );
+        assert_eq!(
+            pipeline.uwuify(input).unwrap(),
+            "nani vibes!, OwO @Laura? OwO weawwy"
+        );
+        assert_eq!(
+            pipeline.uwuify(input).unwrap(),
+            pipeline.uwuify(input).unwrap()
+        );
+
+        let mut unknown = UwuPipelineBuilder::new(engine.clone());
+        unknown.remove("slang");
+        let mut unknown_other = UwuPipelineBuilder::new(engine.clone());
+        unknown_other.move_after("stutter", "slang");
+        let mut duplicate = UwuPipelineBuilder::new(engine);
+        duplicate.push(Slang).push(Slang);
+        for builder in [unknown, unknown_other, duplicate] {
+            assert!(matches!(
+                builder.build(),
+                Err(UwuError::InvalidPipeline(..))
+            ));
+        }
+    }
+
+    //noinspection SpellCheckingInspection
+    #[test]
+    fn assert_nya_before_expressions() {
+        let engine = Uwu::builder()
+            .expressions()
+            .nya()
+            .protect_mentions()
+            .build_engine()
+            .unwrap();
+        let pipeline = UwuPipelineBuilder::new(engine)
+            .move_before("nya", "expressions")
+            .build()
+            .unwrap();
+        // The ranges replaced on a piece must not leak into the next one
+        assert_eq!(pipeline.uwuify("cute @x nope").unwrap(), "kawaii~ @x nyope");
+        assert_eq!(
+            pipeline.uwuify("what what what @x é").unwrap(),
+            "nani nani nani @x é"
+        );
+    }
+
+    //noinspection SpellCheckingInspection
+    #[test]
+    fn assert_stages_match_fused() {
+        const WORDS: [&str; 31] = [
+            "What",
+            "quick",
+            "lovely",
+            "Cute",
+            "nice",
+            "Anime",
+            "none",
+            "hello",
+            "small",
+            "Rust",
+            "ΣΑΣ",
+            "élan",
+            "こんにちは",
+            "কিছু",
+            "\u{1112}\u{1161}\u{11ab}\u{1100}\u{116e}\u{11a8}",
+            "@Laura",
+            "#rust",
+            "https://example.com/a?b=c",
+            "me@example.com",
+            ":smile:",
+            "(",
+            "“",
+            "!",
+            ",",
+            ".",
+            "?",
+            "。",
+            "~",
+            "\n",
+            "\t",
+            "",
+        ];
+
+        // The built-in stages must match the fused pipeline when they run as trait objects
+        let mut rng = fastrand::Rng::with_seed(2);
+        for round in 0..300 {
+            let input = (0..rng.usize(0..20))
+                .map(|_| {
+                    let word = rng.choice(WORDS).unwrap();
+                    let separator = rng.choice(["", " ", ", ", ". ", "  ", "\n"]).unwrap();
+                    format!("{word}{separator}")
+                })
+                .collect::<String>();
+
+            let mut builder = Uwu::builder();
+            builder.seed(crate::Seed::Fixed(round));
+            if rng.bool() {
+                builder.lowercase();
+            }
+            if rng.bool() {
+                builder.expressions();
+            }
+            if rng.bool() {
+                builder.nya().add_nya_exception("anime");
+            }
+            if rng.bool() {
+                builder.w_replace();
+            }
+            if rng.bool() {
+                builder.stutter(rng.u8(1..4));
+            }
+            if rng.bool() {
+                builder.emojis(rng.u8(1..3));
+            }
+            if rng.bool() {
+                builder
+                    .protection(crate::Protection::ALL)
+                    .protect_word("Rust");
+            }
+            let uwu = builder.build();
+
+            let mut pipeline = UwuPipelineBuilder::new(uwu.compile().unwrap())
+                .build()
+                .unwrap();
+            pipeline.fused = false;
+            assert_eq!(
+                pipeline.uwuify(&input).unwrap(),
+                uwu.uwuify(&input).unwrap(),
+                "input: {input:?}, uwu: {uwu:?}"
+            );
+        }
+    }
+
+    //noinspection SpellCheckingInspection
+    #[test]
+    fn assert_custom_stages_protected() {
+        struct Edges;
+
+        impl Transform for Edges {
+            fn name(&self) -> &str {
+                "edges"
+            }
+
+            fn transform(&self, text: &str, _context: &mut TransformContext) -> String {
+                format!("<{text}>")
+            }
+        }
+
+        let engine = Uwu::builder().protect_mentions().build_engine().unwrap();
+        let pipeline = UwuPipelineBuilder::new(engine).push(Edges).build().unwrap();
+        // Custom stages see every piece as is, without the padding of the built-in stages
+        assert_eq!(pipeline.uwuify("hi").unwrap(), "<hi>");
+        assert_eq!(
+            pipeline.uwuify("synergy @Laura synergy").unwrap(),
+            "<synergy >@Laura< synergy>"
+        );
+        assert_eq!(
+            pipeline
+                .uwuify_protecting("synergy#synergy", &[ProtectedSpan::Bytes(7..8)])
+                .unwrap(),
+            "<synergy>#<synergy>"
+        );
+
+        // The built-in stages before them still see the start of the input
+        let engine = Uwu::builder().stutter(1).build_engine().unwrap();
+        let pipeline = UwuPipelineBuilder::new(engine).push(Edges).build().unwrap();
+        assert_eq!(pipeline.uwuify("hi").unwrap(), "<h-hi>");
+    }
+}